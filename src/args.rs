@@ -1,4 +1,4 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -6,6 +6,8 @@ use std::path::PathBuf;
 #[derive(Parser, Debug, Serialize, Deserialize, Clone)]
 #[command(version, about, long_about = None)]
 pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
     /// Do not apply any styles
     #[arg(short, long)]
     pub no_style: bool,
@@ -27,15 +29,100 @@ pub struct Args {
     /// Value to display when network is offline
     #[arg(short, long)]
     pub offline_string: Option<String>,
+    /// Color of offline_string
+    #[arg(long)]
+    pub offline_color: Option<String>,
+    /// Value to display when the link is up without a default route
+    #[arg(long)]
+    pub no_gateway_string: Option<String>,
+    /// Color of no_gateway_string
+    #[arg(long)]
+    pub no_gateway_color: Option<String>,
+    /// Value to display when a captive portal intercepts the traffic
+    #[arg(long)]
+    pub captive_portal_string: Option<String>,
+    /// Color of captive_portal_string
+    #[arg(long)]
+    pub captive_portal_color: Option<String>,
+    /// Plain HTTP URL responding with 204 No Content, to detect captive portals
+    #[arg(long)]
+    pub captive_portal_url: Option<String>,
     /// Value to display when split tunnel is set
     #[arg(short, long)]
     pub split_tunnel_string: Option<String>,
     /// Color of split_tunnel_string
     #[arg(long)]
     pub split_tunnel_color: Option<String>,
+    /// Value to display when only mesh overlay networks are connected
+    #[arg(long)]
+    pub mesh_string: Option<String>,
+    /// Color of mesh_string
+    #[arg(long)]
+    pub mesh_color: Option<String>,
+    /// Value to display when the tunnel transmits without receiving anything
+    #[arg(long)]
+    pub stalled_string: Option<String>,
+    /// Color of stalled_string
+    #[arg(long)]
+    pub stalled_color: Option<String>,
+    /// Value to display when a VPN client runs but its tunnel is not up yet
+    #[arg(long)]
+    pub connecting_string: Option<String>,
+    /// Color of connecting_string
+    #[arg(long)]
+    pub connecting_color: Option<String>,
+    /// Value to display when the default route goes through fewer tunnels than required
+    #[arg(long)]
+    pub short_chain_string: Option<String>,
+    /// Color of short_chain_string
+    #[arg(long)]
+    pub short_chain_color: Option<String>,
+    /// Value to display when destinations are routed against the tunnel policy
+    #[arg(long)]
+    pub violation_string: Option<String>,
+    /// Color of violation_string
+    #[arg(long)]
+    pub violation_color: Option<String>,
+    /// Value to display when the VPN is disabled on a trusted network
+    #[arg(long)]
+    pub trusted_string: Option<String>,
+    /// Color of trusted_string
+    #[arg(long)]
+    pub trusted_color: Option<String>,
     /// Output format
     #[arg(short = 'f', long)]
     pub output_format: Option<String>,
+    /// Only check this tunnel interface, ignoring all other tunnels
+    #[arg(short, long)]
+    pub interface: Option<String>,
+    /// Name patterns of the interfaces which may be tunnels, comma separated
+    #[arg(long, value_delimiter = ',')]
+    pub include_interfaces: Option<Vec<String>>,
+    /// Name patterns of the interfaces which are never tunnels, comma separated
+    #[arg(long, value_delimiter = ',')]
+    pub exclude_interfaces: Option<Vec<String>>,
+    /// Minimum number of nested tunnels the default route has to go through
+    #[arg(long)]
+    pub min_hops: Option<usize>,
+    /// Count unencrypted GRE, IPIP and SIT tunnels as VPN tunnels
+    #[arg(long)]
+    pub ip_tunnels: bool,
+    /// Networks and hosts which must be reached through a tunnel, comma separated
+    #[arg(long, value_delimiter = ',')]
+    pub must_tunnel: Option<Vec<String>>,
+    /// Networks and hosts which must be reached outside of the tunnels, comma separated
+    #[arg(long, value_delimiter = ',')]
+    pub must_bypass: Option<Vec<String>>,
+    /// Networks which do not need a VPN, by gateway MAC address, gateway address or network,
+    /// or Wi-Fi SSID, comma separated
+    #[arg(long, value_delimiter = ',')]
+    pub trusted_networks: Option<Vec<String>>,
+    /// Check the network namespace of this process, e.g. the main process of a container
+    #[arg(long, conflicts_with = "netns")]
+    pub pid: Option<u32>,
+    /// Check the network namespace at this path, e.g. /run/netns/<name>
+    #[arg(long)]
+    pub netns: Option<PathBuf>,
     /// Path to configuration file
     #[arg(short, long)]
     pub config_path: Option<PathBuf>,
@@ -44,6 +131,26 @@ pub struct Args {
     pub lookup: bool,
 }
 
+/// Subcommands, the VPN status is printed without one
+#[derive(Subcommand, Debug, Serialize, Deserialize, Clone)]
+pub enum Command {
+    /// Check whether traffic to a destination goes through the VPN
+    Route {
+        /// IP address or host name
+        destination: String,
+    },
+    /// Explain the VPN status with the signals behind it
+    Report,
+    /// Check the VPN status of a running Docker or Podman container
+    Container {
+        /// Container name or ID
+        name: String,
+        /// Socket of the container engine API, e.g. /run/user/1000/podman/podman.sock
+        #[arg(long)]
+        socket: Option<PathBuf>,
+    },
+}
+
 impl Args {
     /// Parse CLI arguments.
     pub fn parse_args() -> Self {
@@ -78,4 +185,101 @@ mod tests {
         assert_eq!(config.disabled_color, Some("yellow".to_string()));
         assert_eq!(config.offline_string, Some("offline".to_string()));
     }
+
+    #[test]
+    fn parse_route_command() {
+        let args = vec!["vpn_status", "--no-style", "route", "github.com"];
+
+        let config = Args::parse_from(args);
+        assert!(config.no_style);
+        assert!(matches!(
+            config.command,
+            Some(Command::Route { destination }) if destination == "github.com"
+        ));
+    }
+
+    #[test]
+    fn parse_interface_filters() {
+        let args = vec![
+            "vpn_status",
+            "--exclude-interfaces",
+            "cni*,flannel*",
+            "--include-interfaces",
+            "wg0",
+        ];
+
+        let config = Args::parse_from(args);
+        assert_eq!(
+            config.exclude_interfaces,
+            Some(vec!["cni*".to_string(), "flannel*".to_string()])
+        );
+        assert_eq!(config.include_interfaces, Some(vec!["wg0".to_string()]));
+    }
+
+    #[test]
+    fn parse_tunnel_policy() {
+        let args = vec![
+            "vpn_status",
+            "--must-tunnel",
+            "10.0.0.0/8,git.corp.example",
+            "--must-bypass",
+            "192.168.1.20",
+        ];
+
+        let config = Args::parse_from(args);
+        assert_eq!(
+            config.must_tunnel,
+            Some(vec![
+                "10.0.0.0/8".to_string(),
+                "git.corp.example".to_string()
+            ])
+        );
+        assert_eq!(config.must_bypass, Some(vec!["192.168.1.20".to_string()]));
+    }
+
+    #[test]
+    fn parse_trusted_networks() {
+        let args = vec![
+            "vpn_status",
+            "--trusted-networks",
+            "00:1a:2b:3c:4d:5e,192.168.1.0/24,Home",
+            "--trusted-string",
+            "home",
+        ];
+
+        let config = Args::parse_from(args);
+        assert_eq!(
+            config.trusted_networks,
+            Some(vec![
+                "00:1a:2b:3c:4d:5e".to_string(),
+                "192.168.1.0/24".to_string(),
+                "Home".to_string()
+            ])
+        );
+        assert_eq!(config.trusted_string, Some("home".to_string()));
+    }
+
+    #[test]
+    fn parse_namespace() {
+        let config = Args::parse_from(vec!["vpn_status", "--pid", "4242"]);
+        assert_eq!(config.pid, Some(4242));
+
+        let config = Args::parse_from(vec!["vpn_status", "--netns", "/run/netns/vpn"]);
+        assert_eq!(config.netns, Some(PathBuf::from("/run/netns/vpn")));
+
+        let args = vec!["vpn_status", "--pid", "4242", "--netns", "/run/netns/vpn"];
+        assert!(Args::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn parse_container_command() {
+        let args = vec!["vpn_status", "-f", "{status} {ip}", "container", "gluetun"];
+
+        let config = Args::parse_from(args);
+        assert_eq!(config.output_format, Some("{status} {ip}".to_string()));
+        assert!(matches!(
+            config.command,
+            Some(Command::Container { name, socket: None }) if name == "gluetun"
+        ));
+    }
 }
@@ -46,6 +46,36 @@ pub fn get(args: Args) -> Config {
     if args.offline_string.is_some() {
         config.offline_string = args.offline_string;
     }
+    if let Some(offline_color) = args.offline_color {
+        if let Some(ref mut offline_style) = config.offline_style {
+            offline_style.color = offline_color;
+        } else {
+            config.offline_style = Some(StyleConfig::new(&offline_color));
+        }
+    }
+    if args.no_gateway_string.is_some() {
+        config.no_gateway_string = args.no_gateway_string;
+    }
+    if let Some(no_gateway_color) = args.no_gateway_color {
+        if let Some(ref mut no_gateway_style) = config.no_gateway_style {
+            no_gateway_style.color = no_gateway_color;
+        } else {
+            config.no_gateway_style = Some(StyleConfig::new(&no_gateway_color));
+        }
+    }
+    if args.captive_portal_string.is_some() {
+        config.captive_portal_string = args.captive_portal_string;
+    }
+    if let Some(captive_portal_color) = args.captive_portal_color {
+        if let Some(ref mut captive_portal_style) = config.captive_portal_style {
+            captive_portal_style.color = captive_portal_color;
+        } else {
+            config.captive_portal_style = Some(StyleConfig::new(&captive_portal_color));
+        }
+    }
+    if args.captive_portal_url.is_some() {
+        config.captive_portal_url = args.captive_portal_url;
+    }
     if args.split_tunnel_string.is_some() {
         config.split_tunnel_string = args.split_tunnel_string;
     }
@@ -56,9 +86,93 @@ pub fn get(args: Args) -> Config {
             config.split_tunnel_style = Some(StyleConfig::new(&split_tunnel_color));
         }
     }
+    if args.mesh_string.is_some() {
+        config.mesh_string = args.mesh_string;
+    }
+    if let Some(mesh_color) = args.mesh_color {
+        if let Some(ref mut mesh_style) = config.mesh_style {
+            mesh_style.color = mesh_color;
+        } else {
+            config.mesh_style = Some(StyleConfig::new(&mesh_color));
+        }
+    }
+    if args.stalled_string.is_some() {
+        config.stalled_string = args.stalled_string;
+    }
+    if let Some(stalled_color) = args.stalled_color {
+        if let Some(ref mut stalled_style) = config.stalled_style {
+            stalled_style.color = stalled_color;
+        } else {
+            config.stalled_style = Some(StyleConfig::new(&stalled_color));
+        }
+    }
+    if args.connecting_string.is_some() {
+        config.connecting_string = args.connecting_string;
+    }
+    if let Some(connecting_color) = args.connecting_color {
+        if let Some(ref mut connecting_style) = config.connecting_style {
+            connecting_style.color = connecting_color;
+        } else {
+            config.connecting_style = Some(StyleConfig::new(&connecting_color));
+        }
+    }
+    if args.short_chain_string.is_some() {
+        config.short_chain_string = args.short_chain_string;
+    }
+    if let Some(short_chain_color) = args.short_chain_color {
+        if let Some(ref mut short_chain_style) = config.short_chain_style {
+            short_chain_style.color = short_chain_color;
+        } else {
+            config.short_chain_style = Some(StyleConfig::new(&short_chain_color));
+        }
+    }
+    if args.violation_string.is_some() {
+        config.violation_string = args.violation_string;
+    }
+    if let Some(violation_color) = args.violation_color {
+        if let Some(ref mut violation_style) = config.violation_style {
+            violation_style.color = violation_color;
+        } else {
+            config.violation_style = Some(StyleConfig::new(&violation_color));
+        }
+    }
+    if args.trusted_string.is_some() {
+        config.trusted_string = args.trusted_string;
+    }
+    if let Some(trusted_color) = args.trusted_color {
+        if let Some(ref mut trusted_style) = config.trusted_style {
+            trusted_style.color = trusted_color;
+        } else {
+            config.trusted_style = Some(StyleConfig::new(&trusted_color));
+        }
+    }
     if args.output_format.is_some() {
         config.output_format = args.output_format;
     }
+    if args.interface.is_some() {
+        config.interface = args.interface;
+    }
+    if args.include_interfaces.is_some() {
+        config.include_interfaces = args.include_interfaces;
+    }
+    if args.exclude_interfaces.is_some() {
+        config.exclude_interfaces = args.exclude_interfaces;
+    }
+    if args.must_tunnel.is_some() {
+        config.must_tunnel = args.must_tunnel;
+    }
+    if args.must_bypass.is_some() {
+        config.must_bypass = args.must_bypass;
+    }
+    if args.min_hops.is_some() {
+        config.min_hops = args.min_hops;
+    }
+    if args.trusted_networks.is_some() {
+        config.trusted_networks = args.trusted_networks;
+    }
+    if args.ip_tunnels {
+        config.ip_tunnels = Some(args.ip_tunnels);
+    }
     if args.lookup {
         config.lookup = Some(args.lookup);
     }
@@ -1,6 +1,12 @@
-use args::Args;
+use args::{Args, Command};
 use color_eyre::Result;
 use log::{debug, error};
+use std::collections::HashSet;
+use std::net::ToSocketAddrs;
+use vpn_status_lib::chain;
+use vpn_status_lib::config::Config;
+use vpn_status_lib::namespace::Namespace;
+use vpn_status_lib::source::{FilteredSource, InterfaceSource, NetdevSource};
 
 mod args;
 mod config;
@@ -18,11 +24,35 @@ fn main() -> Result<()> {
         return open_config();
     }
 
-    debug!("tunnel_name: {:?}", vpn_status_lib::tunnel_name());
-    debug!("tunnel_address: {:?}", vpn_status_lib::tunnel_address());
+    let mut config = config::get(args.clone());
+    let namespace = namespace(&args)?;
+    // keep the state of the host between runs, next to the lookup cache by default
+    if namespace.is_none() && config.state_path.is_none() {
+        config.state_path = Some(vpn_status_lib::state::default_state_path());
+    }
+
+    if let Some(Command::Route { ref destination }) = args.command {
+        return route(source(&namespace)?.as_ref(), &config, destination);
+    }
+    if let Some(Command::Report) = args.command {
+        return report(source(&namespace)?.as_ref(), &config);
+    }
 
-    let config = config::get(args.clone());
-    let output = match vpn_status_lib::status_string(config, args.no_style) {
+    let status_string = match namespace {
+        Some(ref namespace) => vpn_status_lib::status_string_in(namespace, config, args.no_style),
+        None => {
+            debug!(
+                "tunnel_name: {:?}",
+                vpn_status_lib::tunnel_name_with_config(&NetdevSource, &config)
+            );
+            debug!(
+                "tunnel_address: {:?}",
+                vpn_status_lib::tunnel_address_with_config(&NetdevSource, &config)
+            );
+            vpn_status_lib::status_string(config.clone(), args.no_style)
+        }
+    };
+    let output = match status_string {
         Ok(v) => v,
         Err(e) => {
             error!("error: {}", e);
@@ -34,6 +64,94 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+/// Get the network namespace to check from the arguments, none for the host
+fn namespace(args: &Args) -> Result<Option<Namespace>> {
+    if let Some(Command::Container {
+        ref name,
+        ref socket,
+    }) = args.command
+    {
+        let pid = match socket {
+            Some(socket) => vpn_status_lib::container::pid_with(socket, name)?,
+            None => vpn_status_lib::container::pid(name)?,
+        };
+        debug!("container {} runs as {}", name, pid);
+        return Ok(Some(Namespace::Pid(pid)));
+    }
+    Ok(match (args.pid, &args.netns) {
+        (Some(pid), _) => Some(Namespace::Pid(pid)),
+        (None, Some(path)) => Some(Namespace::Path(path.clone())),
+        (None, None) => None,
+    })
+}
+
+/// Get the interface source of the network namespace, or of the host
+fn source(namespace: &Option<Namespace>) -> Result<Box<dyn InterfaceSource>> {
+    match namespace {
+        Some(namespace) => Ok(Box::new(namespace.capture()?)),
+        None => Ok(Box::new(NetdevSource)),
+    }
+}
+
+/// Print the egress of the traffic to each address of the destination
+fn route(source: &dyn InterfaceSource, config: &Config, destination: &str) -> Result<()> {
+    let source = FilteredSource::from_config(source, config);
+    // the resolver may list an address once per socket type, interleaved with other addresses
+    let mut seen = HashSet::new();
+    let addresses: Vec<_> = (destination, 0)
+        .to_socket_addrs()?
+        .map(|address| address.ip())
+        .filter(|address| seen.insert(*address))
+        .collect();
+
+    for address in addresses {
+        let egress = vpn_status_lib::route_to_with(&source, address);
+        let via = match (egress.interface(), egress.gateway()) {
+            (Some(interface), Some(gateway)) => format!("via {} dev {}", gateway, interface),
+            (Some(interface), None) => format!("dev {}", interface),
+            (None, _) => "unreachable".to_string(),
+        };
+        let verdict = if egress.tunneled() {
+            "tunneled"
+        } else {
+            "not tunneled"
+        };
+        println!("{} {}: {}", address, via, verdict);
+    }
+    Ok(())
+}
+
+/// Print the verdict of each detection signal and the confidence in the status
+fn report(source: &dyn InterfaceSource, config: &Config) -> Result<()> {
+    let report = vpn_status_lib::report_with(source, config)?;
+    println!(
+        "{} ({:.0}% confidence)",
+        report.status,
+        report.confidence * 100.0
+    );
+    for signal in report.signals {
+        let agreement = if signal.agrees == Some(false) {
+            ", contradicts"
+        } else {
+            ""
+        };
+        println!(
+            "{}: {}{} - {}",
+            signal.signal, signal.verdict, agreement, signal.detail
+        );
+    }
+    for warning in &report.detection.warnings {
+        println!("warning: {}", warning);
+    }
+    if let Some(ref trusted) = report.detection.trusted {
+        println!("trusted network: {}", trusted);
+    }
+    if report.detection.chain.len() > 1 {
+        println!("chain: {}", chain::format(&report.detection.chain));
+    }
+    Ok(())
+}
+
 /// Open the default configuration file in the default editor
 fn open_config() -> Result<()> {
     let config_path = confy::get_configuration_file_path("vpn_status", Some("config"))?;
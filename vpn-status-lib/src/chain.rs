@@ -0,0 +1,144 @@
+use crate::routes::{self, AddressFamily, Route};
+use crate::rules::Rule;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+/// Interface in a chain of tunnels nested in each other
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hop {
+    pub interface: String,
+    /// whether the interface is a tunnel, only the last hop is not
+    pub tunnel: bool,
+    /// remote endpoint of the tunnel, routed over the next hop
+    pub endpoint: Option<IpAddr>,
+}
+
+/// Follow the tunnels nested in each other, from a tunnel to the interface carrying the
+/// encrypted traffic of the outermost tunnel, e.g. `wg0 → tun0 → eth0`.
+///
+/// The traffic of each tunnel is routed to its endpoint outside of the tunnels before it.
+/// Tunnels without a known endpoint are expected to be reached over the default route.
+///
+/// # Example
+/// ```rust
+/// # use std::collections::HashMap;
+/// # use vpn_status_lib::chain;
+/// # use vpn_status_lib::routes::Route;
+/// let routes = vec![
+///     Route::new("0.0.0.0/0".parse().unwrap(), "wg0"),
+///     Route::new("198.51.100.7/32".parse().unwrap(), "tun0"),
+///     Route::new("0.0.0.0/1".parse().unwrap(), "tun0"),
+///     Route::new("0.0.0.0/0".parse().unwrap(), "eth0"),
+/// ];
+/// let tunnels = vec!["wg0".to_string(), "tun0".to_string()];
+/// let endpoints = HashMap::from([("wg0".to_string(), "198.51.100.7".parse().unwrap())]);
+/// let hops = chain::resolve("wg0", &tunnels, &endpoints, &routes, &[]);
+/// assert_eq!(chain::format(&hops), "wg0 → tun0 → eth0");
+/// ```
+pub fn resolve(
+    start: &str,
+    tunnels: &[String],
+    endpoints: &HashMap<String, IpAddr>,
+    routes: &[Route],
+    rules: &[Rule],
+) -> Vec<Hop> {
+    let mut chain: Vec<Hop> = vec![];
+    let mut interface = start.to_string();
+    loop {
+        let tunnel = tunnels.contains(&interface);
+        let endpoint = endpoints.get(&interface).copied();
+        chain.push(Hop {
+            interface,
+            tunnel,
+            endpoint,
+        });
+        if !tunnel {
+            break;
+        }
+
+        // a tunnel can not carry its own traffic, which also ends loops
+        let outside: Vec<Route> = routes
+            .iter()
+            .filter(|route| !chain.iter().any(|hop| hop.interface == route.interface))
+            .cloned()
+            .collect();
+        let destination = endpoint.unwrap_or(AddressFamily::Ipv4.unspecified());
+        match routes::lookup(&outside, rules, destination) {
+            Some(matched) => interface = matched.route.interface,
+            None => break,
+        }
+    }
+    chain
+}
+
+/// Count the tunnels of a chain
+pub fn hops(chain: &[Hop]) -> usize {
+    chain.iter().filter(|hop| hop.tunnel).count()
+}
+
+/// Format a chain as its interfaces from the innermost tunnel outwards
+pub fn format(chain: &[Hop]) -> String {
+    chain
+        .iter()
+        .map(|hop| hop.interface.as_str())
+        .collect::<Vec<_>>()
+        .join(" → ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::RuleAction;
+
+    fn route(destination: &str, interface: &str) -> Route {
+        Route::new(destination.parse().unwrap(), interface)
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn single_tunnel() {
+        let routes = vec![route("0.0.0.0/1", "tun0"), route("0.0.0.0/0", "eth0")];
+        let chain = resolve("tun0", &names(&["tun0"]), &HashMap::new(), &routes, &[]);
+        assert_eq!(format(&chain), "tun0 → eth0");
+        assert_eq!(hops(&chain), 1);
+        assert!(!chain[1].tunnel);
+    }
+
+    #[test]
+    fn wireguard_over_openvpn() {
+        // wg-quick routes everything without its firewall mark into its own table
+        let mut wg_table = route("0.0.0.0/0", "wg0");
+        wg_table.table = 51820;
+        let routes = vec![
+            wg_table,
+            route("0.0.0.0/1", "tun0"),
+            route("128.0.0.0/1", "tun0"),
+            route("203.0.113.9/32", "eth0"),
+            route("0.0.0.0/0", "eth0"),
+        ];
+        let mut rule = Rule::new(AddressFamily::Ipv4, 32764, RuleAction::Lookup(51820));
+        rule.fwmark = Some(0xca6c);
+        rule.invert = true;
+        let mut rules = crate::rules::default_rules(AddressFamily::Ipv4);
+        rules.push(rule);
+
+        let endpoints = HashMap::from([("wg0".to_string(), "185.65.134.80".parse().unwrap())]);
+        let chain = resolve("wg0", &names(&["wg0", "tun0"]), &endpoints, &routes, &rules);
+        assert_eq!(format(&chain), "wg0 → tun0 → eth0");
+        assert_eq!(hops(&chain), 2);
+        assert_eq!(chain[0].endpoint, Some("185.65.134.80".parse().unwrap()));
+    }
+
+    #[test]
+    fn unreachable_endpoint() {
+        let endpoints = HashMap::from([("wg0".to_string(), "185.65.134.80".parse().unwrap())]);
+        let routes = vec![route("0.0.0.0/0", "wg0")];
+        let chain = resolve("wg0", &names(&["wg0"]), &endpoints, &routes, &[]);
+        assert_eq!(format(&chain), "wg0");
+        assert_eq!(hops(&chain), 1);
+    }
+}
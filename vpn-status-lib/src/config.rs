@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::default::Default;
+use std::path::PathBuf;
 
 /// Struct to hold configuration
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -16,8 +18,61 @@ pub struct Config {
     pub split_tunnel_string: Option<String>,
     /// style configuration for split_tunnel_string
     pub split_tunnel_style: Option<StyleConfig>,
+    /// value to display when only mesh overlay networks are connected
+    pub mesh_string: Option<String>,
+    /// style configuration for mesh_string
+    pub mesh_style: Option<StyleConfig>,
+    /// value to display when the tunnel transmits without receiving anything
+    pub stalled_string: Option<String>,
+    /// style configuration for stalled_string
+    pub stalled_style: Option<StyleConfig>,
+    /// value to display when a VPN client runs but its tunnel is not up yet
+    pub connecting_string: Option<String>,
+    /// style configuration for connecting_string
+    pub connecting_style: Option<StyleConfig>,
+    /// value to display when the default route goes through fewer tunnels than min_hops
+    pub short_chain_string: Option<String>,
+    /// style configuration for short_chain_string
+    pub short_chain_style: Option<StyleConfig>,
+    /// value to display when destinations are routed against must_tunnel or must_bypass
+    pub violation_string: Option<String>,
+    /// style configuration for violation_string
+    pub violation_style: Option<StyleConfig>,
+    /// value to display when the VPN is disabled on a trusted network
+    pub trusted_string: Option<String>,
+    /// style configuration for trusted_string
+    pub trusted_style: Option<StyleConfig>,
+    /// seconds a tunnel may transmit without receiving anything before it is stalled
+    pub stall_timeout: Option<u64>,
+    /// seconds since the latest WireGuard handshake before a tunnel is stalled
+    pub handshake_timeout: Option<u64>,
+    /// format of the uptime: short, long, clock or seconds
+    pub uptime_format: Option<String>,
+    /// file storing the tunnel traffic counters and connection time between runs, none to keep
+    /// no state
+    pub state_path: Option<PathBuf>,
     /// value to display when network is offline
     pub offline_string: Option<String>,
+    /// style configuration for offline_string
+    pub offline_style: Option<StyleConfig>,
+    /// value to display when the link is up without a default route
+    pub no_gateway_string: Option<String>,
+    /// style configuration for no_gateway_string
+    pub no_gateway_style: Option<StyleConfig>,
+    /// value to display when a captive portal intercepts the traffic
+    pub captive_portal_string: Option<String>,
+    /// style configuration for captive_portal_string
+    pub captive_portal_style: Option<StyleConfig>,
+    /// plain HTTP URL responding with 204 No Content, probed while the VPN is not connected
+    pub captive_portal_url: Option<String>,
+    /// value to display when an address family bypasses the tunnel
+    pub leak_string: Option<String>,
+    /// style configuration for leak_string
+    pub leak_style: Option<StyleConfig>,
+    /// value to display when nameservers are reached outside of the tunnel
+    pub dns_leak_string: Option<String>,
+    /// style configuration for dns_leak_string
+    pub dns_leak_style: Option<StyleConfig>,
     /// output format
     pub output_format: Option<String>,
     /// style configuration for output_format
@@ -28,6 +83,27 @@ pub struct Config {
     pub lookup_providers: Option<Vec<String>>,
     /// style configuration for lookup values
     pub lookup_style: Option<StyleConfig>,
+    /// networks of the VPN exit servers, the public IP address is expected in them when enabled
+    pub exit_ranges: Option<Vec<String>>,
+    /// only consider this tunnel interface, ignoring all other tunnels
+    pub interface: Option<String>,
+    /// name patterns of the interfaces which may be tunnels, all interfaces if empty
+    pub include_interfaces: Option<Vec<String>>,
+    /// name patterns of the interfaces which are never tunnels, e.g. container networks
+    pub exclude_interfaces: Option<Vec<String>>,
+    /// count unencrypted GRE, IPIP and SIT tunnels as VPN tunnels
+    pub ip_tunnels: Option<bool>,
+    /// map of interface name patterns to VPN provider names
+    pub providers: Option<HashMap<String, String>>,
+    /// networks and hosts which must be reached through a tunnel, e.g. internal ranges
+    pub must_tunnel: Option<Vec<String>>,
+    /// networks and hosts which must be reached outside of the tunnels, e.g. local printers
+    pub must_bypass: Option<Vec<String>>,
+    /// minimum number of nested tunnels the default route has to go through
+    pub min_hops: Option<usize>,
+    /// networks which do not need a VPN, by gateway MAC address, gateway address or network,
+    /// or Wi-Fi SSID pattern
+    pub trusted_networks: Option<Vec<String>>,
 }
 
 /// Output style configuration
@@ -55,12 +131,48 @@ impl Default for Config {
             disabled_style: Some(StyleConfig::new("red")),
             split_tunnel_string: Some("split".to_string()),
             split_tunnel_style: Some(StyleConfig::new("blue")),
+            mesh_string: Some("mesh".to_string()),
+            mesh_style: Some(StyleConfig::new("cyan")),
+            stalled_string: Some("stalled".to_string()),
+            stalled_style: Some(StyleConfig::new("yellow")),
+            connecting_string: Some("connecting".to_string()),
+            connecting_style: Some(StyleConfig::new("magenta")),
+            short_chain_string: Some("short chain".to_string()),
+            short_chain_style: Some(StyleConfig::new("yellow")),
+            violation_string: Some("violation".to_string()),
+            violation_style: Some(StyleConfig::new("red")),
+            trusted_string: Some("trusted".to_string()),
+            trusted_style: Some(StyleConfig::new("green")),
+            stall_timeout: Some(60),
+            handshake_timeout: Some(180),
+            uptime_format: Some("short".to_string()),
+            state_path: None,
             offline_string: Some("offline".to_string()),
+            offline_style: None,
+            no_gateway_string: Some("no gateway".to_string()),
+            no_gateway_style: Some(StyleConfig::new("red")),
+            captive_portal_string: Some("captive portal".to_string()),
+            captive_portal_style: Some(StyleConfig::new("yellow")),
+            captive_portal_url: None,
+            leak_string: None,
+            leak_style: Some(StyleConfig::new("red")),
+            dns_leak_string: Some("dns leak".to_string()),
+            dns_leak_style: Some(StyleConfig::new("red")),
             output_format: None,
             output_style: None,
             lookup: Some(false),
             lookup_providers: None,
             lookup_style: None,
+            exit_ranges: None,
+            interface: None,
+            include_interfaces: None,
+            exclude_interfaces: None,
+            ip_tunnels: Some(false),
+            providers: None,
+            must_tunnel: None,
+            must_bypass: None,
+            min_hops: None,
+            trusted_networks: None,
         }
     }
 }
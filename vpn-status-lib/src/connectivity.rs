@@ -0,0 +1,142 @@
+use log::debug;
+use netdev::Interface;
+use std::io::{Error, ErrorKind, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+/// Time to wait for the captive portal probe to connect and respond
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Check whether any interface besides loopback is up and has an address
+pub fn has_link(interfaces: &[Interface]) -> bool {
+    interfaces.iter().any(|interface| {
+        interface.is_up()
+            && !interface.is_loopback()
+            && (!interface.ipv4.is_empty()
+                || interface
+                    .ipv6
+                    .iter()
+                    .any(|net| !crate::is_link_local(&net.addr().into())))
+    })
+}
+
+/// Probe a plain HTTP URL which responds with `204 No Content` on an open network.
+///
+/// Captive portals intercept the request and redirect it to their login page or serve the page
+/// instead, so any redirect, other successful response, or `511 Network Authentication Required`
+/// means a portal.
+///
+/// # Example
+/// ```rust,no_run
+/// # use vpn_status_lib::connectivity;
+/// let url = "http://connectivitycheck.gstatic.com/generate_204";
+/// if connectivity::captive_portal(url, connectivity::PROBE_TIMEOUT).unwrap() {
+///     println!("log in to the network first");
+/// }
+/// ```
+pub fn captive_portal(url: &str, timeout: Duration) -> Result<bool, Error> {
+    let status = probe(url, timeout)?;
+    debug!("captive portal probe {} responded with {}", url, status);
+    Ok(matches!(status, 200..=203 | 205..=399 | 511))
+}
+
+/// Send a GET request and get the status code of the response
+fn probe(url: &str, timeout: Duration) -> Result<u16, Error> {
+    let invalid =
+        |message: &str| Error::new(ErrorKind::InvalidInput, format!("{}: {}", message, url));
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or_else(|| invalid("only http urls can be probed"))?;
+    let (authority, path) = match rest.find('/') {
+        Some(index) => rest.split_at(index),
+        None => (rest, "/"),
+    };
+    let authority_port = if authority.ends_with(']') || !authority.contains(':') {
+        format!("{}:80", authority)
+    } else {
+        authority.to_string()
+    };
+    let address = authority_port
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| invalid("host not found"))?;
+
+    let mut stream = TcpStream::connect_timeout(&address, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    let request = format!(
+        "GET {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\nUser-Agent: vpn-status\r\n\r\n",
+        path, authority
+    );
+    stream.write_all(request.as_bytes())?;
+
+    // only the status line is needed
+    let mut response = vec![];
+    let mut buffer = [0; 512];
+    while !response.contains(&b'\n') {
+        let read = stream.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        response.extend_from_slice(&buffer[..read]);
+    }
+    let response = String::from_utf8_lossy(&response);
+    let status_line = response.lines().next().unwrap_or_default();
+    status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid response: {}", status_line),
+            )
+        })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    /// Serve a single response on a local port and get the URL to probe
+    pub(crate) fn serve(response: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = vec![];
+            let mut buffer = [0; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                match stream.read(&mut buffer) {
+                    Ok(0) | Err(_) => break,
+                    Ok(read) => request.extend_from_slice(&buffer[..read]),
+                }
+            }
+            stream.write_all(response.as_bytes()).unwrap();
+        });
+        format!("http://{}/generate_204", address)
+    }
+
+    #[test]
+    fn open_network() {
+        let url = serve("HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n");
+        assert!(!captive_portal(&url, PROBE_TIMEOUT).unwrap());
+    }
+
+    #[test]
+    fn portal_redirect() {
+        let url = serve("HTTP/1.1 302 Found\r\nLocation: http://portal.example/login\r\n\r\n");
+        assert!(captive_portal(&url, PROBE_TIMEOUT).unwrap());
+
+        let url = serve("HTTP/1.0 200 OK\r\n\r\n<html>Welcome to the hotel wifi</html>");
+        assert!(captive_portal(&url, PROBE_TIMEOUT).unwrap());
+    }
+
+    #[test]
+    fn probe_errors() {
+        assert!(captive_portal("https://example.com/", PROBE_TIMEOUT).is_err());
+        let url = serve("garbage");
+        assert!(captive_portal(&url, PROBE_TIMEOUT).is_err());
+    }
+}
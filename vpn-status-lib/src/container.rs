@@ -0,0 +1,190 @@
+use crate::error::VpnStatusError;
+use log::debug;
+use serde::Deserialize;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+
+/// Sockets of the Docker API, and of the Docker compatible Podman API, checked in order
+const SOCKETS: &[&str] = &["/var/run/docker.sock", "/run/podman/podman.sock"];
+
+#[derive(Deserialize)]
+struct Inspect {
+    #[serde(rename = "State")]
+    state: State,
+}
+
+#[derive(Deserialize)]
+struct State {
+    #[serde(rename = "Running")]
+    running: bool,
+    #[serde(rename = "Pid")]
+    pid: u32,
+}
+
+/// Get the sockets of the container engines, the socket set in `DOCKER_HOST` or
+/// `CONTAINER_HOST` first, followed by the system and rootless default sockets
+pub fn sockets() -> Vec<PathBuf> {
+    let mut sockets: Vec<PathBuf> = ["DOCKER_HOST", "CONTAINER_HOST"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .filter_map(|host| host.strip_prefix("unix://").map(PathBuf::from))
+        .collect();
+    sockets.extend(SOCKETS.iter().map(PathBuf::from));
+    if let Ok(runtime) = std::env::var("XDG_RUNTIME_DIR") {
+        sockets.push(Path::new(&runtime).join("podman/podman.sock"));
+        sockets.push(Path::new(&runtime).join("docker.sock"));
+    }
+    sockets
+}
+
+/// Get the PID of the main process of a running container by its name or ID, from the first
+/// container engine socket which exists.
+///
+/// # Example
+/// ```rust,no_run
+/// # use vpn_status_lib::namespace::Namespace;
+/// let pid = vpn_status_lib::container::pid("gluetun").unwrap();
+/// let status = vpn_status_lib::status_in(&Namespace::Pid(pid)).unwrap();
+/// println!("gluetun is {}", status);
+/// ```
+pub fn pid(name: &str) -> Result<u32, VpnStatusError> {
+    let sockets = sockets();
+    let socket = sockets
+        .iter()
+        .find(|socket| socket.exists())
+        .ok_or_else(|| {
+            VpnStatusError::backend("container engine socket", Error::from(ErrorKind::NotFound))
+        })?;
+    pid_with(socket, name)
+}
+
+/// Get the PID of the main process of a running container from the API on the given socket
+#[cfg(unix)]
+pub fn pid_with(socket: &Path, name: &str) -> Result<u32, VpnStatusError> {
+    use std::io::{Read, Write};
+    use std::os::unix::net::UnixStream;
+
+    let backend = format!("container engine {}", socket.display());
+    let error = |error| VpnStatusError::backend(&backend, error);
+    // names and IDs never need escaping, anything else can not be a container
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_.-".contains(c))
+    {
+        return Err(VpnStatusError::ContainerNotFound(name.to_string()));
+    }
+
+    let mut stream = UnixStream::connect(socket).map_err(error)?;
+    // HTTP/1.0 responses are never chunked and end when the connection is closed
+    let request = format!(
+        "GET /containers/{}/json HTTP/1.0\r\nHost: localhost\r\nUser-Agent: vpn-status\r\n\r\n",
+        name
+    );
+    stream.write_all(request.as_bytes()).map_err(error)?;
+    let mut response = vec![];
+    stream.read_to_end(&mut response).map_err(error)?;
+
+    let response = String::from_utf8_lossy(&response);
+    let (head, body) = response.split_once("\r\n\r\n").unwrap_or((&response, ""));
+    let status = head
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .unwrap_or_default();
+    debug!("container {} inspected with status {}", name, status);
+    match status {
+        "200" => {}
+        "404" => return Err(VpnStatusError::ContainerNotFound(name.to_string())),
+        _ => {
+            let message = format!("unexpected response {}: {}", status, body.trim());
+            return Err(error(Error::new(ErrorKind::InvalidData, message)));
+        }
+    }
+
+    let inspect: Inspect = serde_json::from_str(body).map_err(|e| error(e.into()))?;
+    if !inspect.state.running || inspect.state.pid == 0 {
+        return Err(VpnStatusError::ContainerNotRunning(name.to_string()));
+    }
+    Ok(inspect.state.pid)
+}
+
+#[cfg(not(unix))]
+pub fn pid_with(socket: &Path, _name: &str) -> Result<u32, VpnStatusError> {
+    Err(VpnStatusError::backend(
+        &format!("container engine {}", socket.display()),
+        Error::from(ErrorKind::Unsupported),
+    ))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::os::unix::net::UnixListener;
+
+    /// Serve a container engine stand-in on a socket, answering the requests in order
+    fn serve(name: &str, responses: Vec<&'static str>) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("vpn-status-{}-{}.sock", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+        std::thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = vec![];
+                let mut buffer = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    match stream.read(&mut buffer) {
+                        Ok(0) | Err(_) => break,
+                        Ok(read) => request.extend_from_slice(&buffer[..read]),
+                    }
+                }
+                assert!(request.starts_with(b"GET /containers/"));
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        path
+    }
+
+    #[test]
+    fn inspect_container() {
+        let socket = serve(
+            "inspect",
+            vec![
+                concat!(
+                    "HTTP/1.0 200 OK\r\nContent-Type: application/json\r\n\r\n",
+                    r#"{"Id":"4f1c","Name":"/gluetun","State":{"Status":"running","Running":true,"Pid":4242}}"#
+                ),
+                "HTTP/1.0 404 Not Found\r\n\r\n{\"message\":\"No such container: missing\"}",
+                concat!(
+                    "HTTP/1.0 200 OK\r\n\r\n",
+                    r#"{"State":{"Status":"exited","Running":false,"Pid":0}}"#
+                ),
+            ],
+        );
+        assert_eq!(pid_with(&socket, "gluetun").unwrap(), 4242);
+        assert!(matches!(
+            pid_with(&socket, "missing"),
+            Err(VpnStatusError::ContainerNotFound(_))
+        ));
+        assert!(matches!(
+            pid_with(&socket, "stopped"),
+            Err(VpnStatusError::ContainerNotRunning(_))
+        ));
+        assert!(matches!(
+            pid_with(&socket, "../images"),
+            Err(VpnStatusError::ContainerNotFound(_))
+        ));
+        std::fs::remove_file(socket).unwrap();
+    }
+
+    #[test]
+    fn missing_socket() {
+        let socket = Path::new("fixtures/missing.sock");
+        assert!(matches!(
+            pid_with(socket, "gluetun"),
+            Err(VpnStatusError::Backend { .. })
+        ));
+    }
+}
@@ -0,0 +1,316 @@
+use crate::routes::{self, Route};
+use crate::rules::Rule;
+use netdev::Interface;
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::PathBuf;
+
+/// Address of the systemd-resolved stub resolver
+const RESOLVED_STUB: IpAddr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 53));
+
+/// Nameserver used by the host
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Nameserver {
+    pub address: IpAddr,
+    /// interface the queries are sent through, if the nameserver is bound to one
+    pub interface: Option<String>,
+}
+
+impl Nameserver {
+    pub fn new(address: IpAddr) -> Self {
+        Self {
+            address,
+            interface: None,
+        }
+    }
+}
+
+/// Reads the nameserver configuration from `resolv.conf` and systemd-resolved
+#[derive(Debug, Clone)]
+pub struct DnsInspector {
+    /// path of `resolv.conf`
+    pub resolv_conf: PathBuf,
+    /// systemd-resolved runtime directory
+    pub resolved_dir: PathBuf,
+}
+
+impl Default for DnsInspector {
+    fn default() -> Self {
+        Self {
+            resolv_conf: PathBuf::from("/etc/resolv.conf"),
+            resolved_dir: PathBuf::from("/run/systemd/resolve"),
+        }
+    }
+}
+
+impl DnsInspector {
+    pub fn new(resolv_conf: impl Into<PathBuf>, resolved_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            resolv_conf: resolv_conf.into(),
+            resolved_dir: resolved_dir.into(),
+        }
+    }
+
+    /// Get the active nameservers.
+    ///
+    /// When `resolv.conf` points to the systemd-resolved stub, the per-link nameservers
+    /// used for queries without a matching routing domain are returned instead.
+    pub fn nameservers(&self, interfaces: &[Interface]) -> Vec<Nameserver> {
+        let addresses = std::fs::read_to_string(&self.resolv_conf)
+            .map(|content| parse_resolv_conf(&content))
+            .unwrap_or_default();
+        if !addresses.contains(&RESOLVED_STUB) {
+            return addresses.into_iter().map(Nameserver::new).collect();
+        }
+
+        let mut nameservers = self.link_nameservers(interfaces);
+        if nameservers.is_empty() {
+            // fall back to the upstream servers of the stub resolver
+            nameservers = std::fs::read_to_string(self.resolved_dir.join("resolv.conf"))
+                .map(|content| parse_resolv_conf(&content))
+                .unwrap_or_default()
+                .into_iter()
+                .map(Nameserver::new)
+                .collect();
+        }
+        nameservers
+    }
+
+    /// Read the systemd-resolved per-link configuration, stored by interface index
+    fn link_nameservers(&self, interfaces: &[Interface]) -> Vec<Nameserver> {
+        let Ok(entries) = std::fs::read_dir(self.resolved_dir.join("netif")) else {
+            return vec![];
+        };
+        let mut links: Vec<(u32, String)> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let index = entry.file_name().to_str()?.parse().ok()?;
+                Some((index, std::fs::read_to_string(entry.path()).ok()?))
+            })
+            .collect();
+        links.sort();
+
+        let mut nameservers = vec![];
+        for (index, content) in links {
+            let link = parse_link(&content);
+            if !link.default_route {
+                continue;
+            }
+            let interface = interfaces
+                .iter()
+                .find(|interface| interface.index == index)
+                .map(|interface| interface.name.clone());
+            nameservers.extend(link.servers.into_iter().map(|address| Nameserver {
+                address,
+                interface: interface.clone(),
+            }));
+        }
+        nameservers
+    }
+}
+
+/// Parse the nameserver addresses of a `resolv.conf` file
+pub fn parse_resolv_conf(content: &str) -> Vec<IpAddr> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            match fields.next() {
+                Some("nameserver") => parse_server(fields.next()?),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// systemd-resolved configuration of a single link
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkDns {
+    pub servers: Vec<IpAddr>,
+    /// whether the link is used for queries without a matching routing domain
+    pub default_route: bool,
+}
+
+/// Parse a systemd-resolved link file, e.g. `/run/systemd/resolve/netif/2`
+pub fn parse_link(content: &str) -> LinkDns {
+    let mut servers = vec![];
+    let mut domains = vec![];
+    let mut default_route = None;
+    for line in content.lines() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        match key.trim() {
+            "DNS" => servers.extend(value.split_whitespace().filter_map(parse_server)),
+            "DOMAINS" => domains.extend(value.split_whitespace().map(str::to_string)),
+            "DEFAULT_ROUTE" => default_route = Some(value.trim() == "yes"),
+            _ => (),
+        }
+    }
+    // links with only routing domains are not used as default route unless they route "."
+    let default_route = default_route.unwrap_or_else(|| {
+        domains.iter().any(|domain| domain == "~.")
+            || !domains.iter().any(|domain| domain.starts_with('~'))
+    });
+    LinkDns {
+        servers,
+        default_route,
+    }
+}
+
+/// Parse a server address, which may carry a port, interface scope or server name
+fn parse_server(server: &str) -> Option<IpAddr> {
+    let server = server.split('#').next()?;
+    let server = server.split('%').next()?;
+    if let Ok(address) = server.parse() {
+        return Some(address);
+    }
+    // [2001:db8::1]:53 or 192.0.2.1:53
+    let host = match server.strip_prefix('[') {
+        Some(server) => server.split(']').next()?,
+        None => server.rsplit_once(':')?.0,
+    };
+    host.parse().ok()
+}
+
+/// Get the nameservers reached outside of the given tunnel interfaces.
+///
+/// Nameservers bound to an interface are reached through it, others are looked up in
+/// the routing tables. Nameservers without a route, like local resolvers, never leak.
+pub fn leaking(
+    nameservers: &[Nameserver],
+    routes: &[Route],
+    rules: &[Rule],
+    tunnels: &[String],
+) -> Vec<Nameserver> {
+    nameservers
+        .iter()
+        .filter(|nameserver| {
+            let interface = match nameserver.interface {
+                Some(ref interface) => Some(interface.clone()),
+                None => routes::lookup(routes, rules, nameserver.address)
+                    .map(|found| found.route.interface),
+            };
+            interface.is_some_and(|interface| !tunnels.contains(&interface))
+        })
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn interface(name: &str, index: u32) -> Interface {
+        let mut interface = Interface::dummy();
+        interface.name = name.to_string();
+        interface.index = index;
+        interface
+    }
+
+    fn address(address: &str) -> IpAddr {
+        address.parse().unwrap()
+    }
+
+    #[test]
+    fn resolv_conf() {
+        let content = "# generated\nsearch example.com\nnameserver 10.8.0.1\nnameserver fe80::1%eth0\n;nameserver 1.1.1.1\n";
+        assert_eq!(
+            parse_resolv_conf(content),
+            vec![address("10.8.0.1"), address("fe80::1")]
+        );
+    }
+
+    #[test]
+    fn server_formats() {
+        assert_eq!(
+            parse_server("1.1.1.1#cloudflare-dns.com"),
+            Some(address("1.1.1.1"))
+        );
+        assert_eq!(parse_server("192.0.2.1:5353"), Some(address("192.0.2.1")));
+        assert_eq!(
+            parse_server("[2001:db8::1]:53"),
+            Some(address("2001:db8::1"))
+        );
+        assert_eq!(parse_server("2001:db8::1"), Some(address("2001:db8::1")));
+        assert_eq!(parse_server("dns.example.com"), None);
+    }
+
+    #[test]
+    fn link_default_route() {
+        let link = parse_link(
+            "# This is private data. Do not parse.\nDNS=10.2.0.1 10.2.0.2\nDOMAINS=~.\n",
+        );
+        assert_eq!(link.servers, vec![address("10.2.0.1"), address("10.2.0.2")]);
+        assert!(link.default_route);
+
+        assert!(parse_link("DNS=192.168.1.1\nDOMAINS=lan\n").default_route);
+        assert!(!parse_link("DNS=10.0.0.1\nDOMAINS=~corp.example.com\n").default_route);
+        assert!(!parse_link("DNS=192.168.1.1\nDEFAULT_ROUTE=no\n").default_route);
+    }
+
+    #[test]
+    fn plain_resolv_conf() {
+        let inspector = DnsInspector::new("fixtures/dns/plain/resolv.conf", "fixtures/dns/missing");
+        let nameservers = inspector.nameservers(&[]);
+        assert_eq!(
+            nameservers,
+            vec![
+                Nameserver::new(address("10.8.0.1")),
+                Nameserver::new(address("192.168.1.1"))
+            ]
+        );
+    }
+
+    #[test]
+    fn resolved_links() {
+        let inspector = DnsInspector::new("fixtures/dns/stub/resolv.conf", "fixtures/dns/resolve");
+        let interfaces = vec![interface("eth0", 2), interface("wg0", 5)];
+        let nameservers = inspector.nameservers(&interfaces);
+        assert_eq!(
+            nameservers,
+            vec![
+                Nameserver {
+                    address: address("192.168.1.1"),
+                    interface: Some("eth0".to_string()),
+                },
+                Nameserver {
+                    address: address("10.2.0.1"),
+                    interface: Some("wg0".to_string()),
+                },
+            ]
+        );
+
+        let tunnels = vec!["wg0".to_string()];
+        let leaking = leaking(&nameservers, &[], &[], &tunnels);
+        assert_eq!(leaking, vec![nameservers[0].clone()]);
+    }
+
+    #[test]
+    fn resolved_upstream_fallback() {
+        let inspector = DnsInspector::new(
+            "fixtures/dns/stub/resolv.conf",
+            "fixtures/dns/resolve-upstream",
+        );
+        assert_eq!(
+            inspector.nameservers(&[]),
+            vec![Nameserver::new(address("9.9.9.9"))]
+        );
+    }
+
+    #[test]
+    fn routed_nameservers() {
+        let routes = vec![
+            Route::new("0.0.0.0/0".parse().unwrap(), "tun0"),
+            Route::new("192.168.1.0/24".parse().unwrap(), "eth0"),
+        ];
+        let nameservers = vec![
+            Nameserver::new(address("10.8.0.1")),
+            Nameserver::new(address("192.168.1.1")),
+            Nameserver::new(address("127.0.0.1")),
+        ];
+        let tunnels = vec!["tun0".to_string()];
+        let leaking = leaking(&nameservers, &routes, &[], &tunnels);
+        assert_eq!(leaking, vec![Nameserver::new(address("192.168.1.1"))]);
+    }
+}
@@ -1,3 +1,6 @@
+use std::fmt::Display;
+use std::io;
+use std::sync::Arc;
 use thiserror::Error;
 
 /// Error type for VPN status
@@ -5,8 +8,105 @@ use thiserror::Error;
 pub enum VpnStatusError {
     #[error("Failed getting default interface")]
     DefaultInterface(String),
+    #[error("Interface not found: {0}")]
+    InterfaceNotFound(String),
+    #[error("Container not found: {0}")]
+    ContainerNotFound(String),
+    #[error("Container is not running: {0}")]
+    ContainerNotRunning(String),
+    #[error("No network interfaces found")]
+    NoInterfaces,
+    #[error("Permission denied reading {backend}")]
+    PermissionDenied {
+        backend: String,
+        #[source]
+        source: io::Error,
+    },
+    #[error("Failed reading {backend}: {source}")]
+    Backend {
+        backend: String,
+        #[source]
+        source: io::Error,
+    },
     #[error("Failed styling")]
     StyleError(String),
     #[error("Failed performing lookup")]
     LookupError(#[from] public_ip_address::error::Error),
 }
+
+impl VpnStatusError {
+    /// Wrap an error of the backend reading the network state
+    pub fn backend(backend: &str, source: io::Error) -> Self {
+        match source.kind() {
+            io::ErrorKind::PermissionDenied => Self::PermissionDenied {
+                backend: backend.to_string(),
+                source,
+            },
+            _ => Self::Backend {
+                backend: backend.to_string(),
+                source,
+            },
+        }
+    }
+}
+
+/// Error of a backend the detection continued without, e.g. reading the WireGuard peers
+/// without `CAP_NET_ADMIN`
+#[derive(Debug, Clone)]
+pub struct Warning(Arc<VpnStatusError>);
+
+impl Warning {
+    pub fn error(&self) -> &VpnStatusError {
+        &self.0
+    }
+}
+
+impl From<VpnStatusError> for Warning {
+    fn from(error: VpnStatusError) -> Self {
+        Self(Arc::new(error))
+    }
+}
+
+impl PartialEq for Warning {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.to_string() == other.0.to_string()
+    }
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn backend_errors() {
+        let error = io::Error::from_raw_os_error(1);
+        let error = VpnStatusError::backend("wireguard peers", error);
+        assert!(matches!(
+            error,
+            VpnStatusError::PermissionDenied { ref backend, .. } if backend == "wireguard peers"
+        ));
+        assert_eq!(
+            error.source().unwrap().to_string(),
+            io::Error::from_raw_os_error(1).to_string()
+        );
+        assert_eq!(
+            error.to_string(),
+            "Permission denied reading wireguard peers"
+        );
+
+        let error = io::Error::new(io::ErrorKind::InvalidData, "truncated message");
+        let error = VpnStatusError::backend("routes", error);
+        assert_eq!(
+            error.to_string(),
+            "Failed reading routes: truncated message"
+        );
+        assert_eq!(error.source().unwrap().to_string(), "truncated message");
+    }
+}
@@ -0,0 +1,53 @@
+/// Match a name against a shell style pattern, where `*` matches any sequence
+/// of characters and `?` matches a single character.
+pub(crate) fn matches(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // position of the last `*` and the name position it is matched up to
+    let mut star: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            // let the last `*` consume one more character
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literal() {
+        assert!(matches("wg0", "wg0"));
+        assert!(!matches("wg0", "wg01"));
+        assert!(!matches("wg01", "wg0"));
+    }
+
+    #[test]
+    fn wildcards() {
+        assert!(matches("wg*", "wg0"));
+        assert!(matches("wg*", "wg"));
+        assert!(matches("*mullvad*", "wg0-mullvad"));
+        assert!(matches("veth*", "veth1a2b3c"));
+        assert!(!matches("veth*", "eth0"));
+        assert!(matches("tun?", "tun0"));
+        assert!(!matches("tun?", "tun10"));
+        assert!(matches("*", ""));
+        assert!(matches("a*b*c", "axxbyyc"));
+        assert!(!matches("a*b*c", "axxbyy"));
+    }
+}
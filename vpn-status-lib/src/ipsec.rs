@@ -0,0 +1,242 @@
+use crate::routes::{self, AddressFamily};
+use netdev::ipnet::IpNet;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+
+/// Direction of the traffic an IPsec policy applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PolicyDirection {
+    In,
+    Out,
+    Forward,
+}
+
+/// What happens to traffic matching an IPsec policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PolicyAction {
+    /// protect the traffic with the policy templates, or pass it as is without templates
+    Allow,
+    /// drop the traffic
+    Block,
+}
+
+/// Security association required by a policy, as listed by `ip xfrm policy`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Template {
+    /// remote endpoint, unspecified for transport mode templates
+    pub destination: IpAddr,
+    /// request id shared with the matching states, 0 matches any state
+    pub reqid: u32,
+    /// whether the traffic is encapsulated in tunnel mode
+    pub tunnel: bool,
+}
+
+/// IPsec policy, as listed by `ip xfrm policy`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Policy {
+    pub direction: PolicyDirection,
+    /// source traffic selector
+    pub source: IpNet,
+    /// destination traffic selector
+    pub destination: IpNet,
+    /// policies are evaluated in ascending priority order
+    pub priority: u32,
+    pub action: PolicyAction,
+    pub templates: Vec<Template>,
+}
+
+impl Policy {
+    pub fn new(direction: PolicyDirection, source: IpNet, destination: IpNet) -> Self {
+        Self {
+            direction,
+            source,
+            destination,
+            priority: 0,
+            action: PolicyAction::Allow,
+            templates: vec![],
+        }
+    }
+
+    /// Check whether the policy applies to traffic from this host, which uses one of the
+    /// local addresses as source.
+    pub fn matches(&self, local: &[IpAddr], destination: IpAddr) -> bool {
+        self.direction == PolicyDirection::Out
+            && self.destination.contains(&destination)
+            && (self.source.prefix_len() == 0
+                && AddressFamily::of(&self.source.addr()) == AddressFamily::of(&destination)
+                || local.iter().any(|address| self.source.contains(address)))
+    }
+}
+
+/// Security association installed by the key exchange daemon, as listed by `ip xfrm state`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct State {
+    pub source: IpAddr,
+    pub destination: IpAddr,
+    pub spi: u32,
+    pub reqid: u32,
+    /// whether the traffic is encapsulated in tunnel mode
+    pub tunnel: bool,
+}
+
+impl Template {
+    /// Check whether a security association for the template has been negotiated.
+    ///
+    /// Policies installed on demand, like strongSwan's `auto=route`, exist before the
+    /// connection is established.
+    pub fn established(&self, states: &[State]) -> bool {
+        states.iter().any(|state| {
+            (self.reqid == 0 || state.reqid == self.reqid)
+                && (self.destination.is_unspecified() || state.destination == self.destination)
+        })
+    }
+}
+
+/// Find the outbound policy applied to traffic from this host to the destination
+pub fn lookup<'a>(
+    policies: &'a [Policy],
+    local: &[IpAddr],
+    destination: IpAddr,
+) -> Option<&'a Policy> {
+    policies
+        .iter()
+        .filter(|policy| policy.matches(local, destination))
+        .min_by_key(|policy| policy.priority)
+}
+
+/// Check whether traffic to the destination is sent through an established IPsec tunnel
+pub fn protects(
+    policies: &[Policy],
+    states: &[State],
+    local: &[IpAddr],
+    destination: IpAddr,
+) -> bool {
+    lookup(policies, local, destination).is_some_and(|policy| tunnels(policy, states))
+}
+
+fn tunnels(policy: &Policy, states: &[State]) -> bool {
+    policy.action == PolicyAction::Allow
+        && policy.templates.iter().any(|template| template.tunnel)
+        && policy
+            .templates
+            .iter()
+            .all(|template| template.established(states))
+}
+
+/// Get the outbound policies sending traffic through an established IPsec tunnel
+pub fn active_policies(policies: &[Policy], states: &[State]) -> Vec<Policy> {
+    policies
+        .iter()
+        .filter(|policy| policy.direction == PolicyDirection::Out && tunnels(policy, states))
+        .cloned()
+        .collect()
+}
+
+/// Check whether the IPsec policies protect the whole public address space of an address
+/// family, like a road warrior connection with a `0.0.0.0/0` remote traffic selector.
+pub fn covers_public_space(
+    policies: &[Policy],
+    states: &[State],
+    local: &[IpAddr],
+    family: AddressFamily,
+) -> bool {
+    routes::public_samples(family)
+        .into_iter()
+        .all(|destination| protects(policies, states, local, destination))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn net(net: &str) -> IpNet {
+        net.parse().unwrap()
+    }
+
+    fn address(address: &str) -> IpAddr {
+        address.parse().unwrap()
+    }
+
+    /// strongSwan road warrior with a virtual IP and a passthrough policy for the LAN
+    fn road_warrior(remote: &str) -> Vec<Policy> {
+        let mut tunnel = Policy::new(PolicyDirection::Out, net("10.10.0.5/32"), net(remote));
+        tunnel.priority = 375423;
+        tunnel.templates.push(Template {
+            destination: address("203.0.113.1"),
+            reqid: 1,
+            tunnel: true,
+        });
+        let mut passthrough = Policy::new(
+            PolicyDirection::Out,
+            net("192.168.1.0/24"),
+            net("192.168.1.0/24"),
+        );
+        passthrough.priority = 1000;
+        vec![tunnel, passthrough]
+    }
+
+    fn states() -> Vec<State> {
+        vec![State {
+            source: address("192.168.1.10"),
+            destination: address("203.0.113.1"),
+            spi: 0xc1a2b3c4,
+            reqid: 1,
+            tunnel: true,
+        }]
+    }
+
+    #[test]
+    fn full_tunnel() {
+        let policies = road_warrior("0.0.0.0/0");
+        let local = vec![address("192.168.1.10"), address("10.10.0.5")];
+        assert!(covers_public_space(
+            &policies,
+            &states(),
+            &local,
+            AddressFamily::Ipv4
+        ));
+        assert!(!covers_public_space(
+            &policies,
+            &states(),
+            &local,
+            AddressFamily::Ipv6
+        ));
+        assert!(!protects(
+            &policies,
+            &states(),
+            &local,
+            address("192.168.1.1")
+        ));
+        assert_eq!(active_policies(&policies, &states()).len(), 1);
+    }
+
+    #[test]
+    fn split_tunnel() {
+        let policies = road_warrior("10.0.0.0/8");
+        let local = vec![address("192.168.1.10"), address("10.10.0.5")];
+        assert!(protects(&policies, &states(), &local, address("10.1.2.3")));
+        assert!(!covers_public_space(
+            &policies,
+            &states(),
+            &local,
+            AddressFamily::Ipv4
+        ));
+        assert_eq!(active_policies(&policies, &states()).len(), 1);
+    }
+
+    #[test]
+    fn not_established() {
+        let policies = road_warrior("0.0.0.0/0");
+        let local = vec![address("192.168.1.10"), address("10.10.0.5")];
+        assert!(!protects(&policies, &[], &local, address("1.1.1.1")));
+        assert!(active_policies(&policies, &[]).is_empty());
+    }
+
+    #[test]
+    fn foreign_source_selector() {
+        // site to site policies for another subnet do not apply to local traffic
+        let policies = road_warrior("0.0.0.0/0");
+        let local = vec![address("192.168.1.10")];
+        assert!(!protects(&policies, &states(), &local, address("1.1.1.1")));
+    }
+}
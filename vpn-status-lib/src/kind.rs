@@ -0,0 +1,322 @@
+use crate::glob;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::path::Path;
+
+const ARPHRD_PPP: u16 = 512;
+/// ARPHRD types of IPIP, IPv6 in IPv6, SIT and GRE tunnels
+const ARPHRD_IP_TUNNELS: &[u16] = &[768, 769, 776, 778, 823];
+
+/// Names of the IP tunnel devices created by iproute2 and the fallback devices of their modules
+const IP_TUNNEL_NAMES: &[&str] = &[
+    "gre*", "gretap*", "erspan*", "ip6gre*", "ipip*", "tunl*", "sit*", "ip6tnl*",
+];
+
+/// Kind of VPN behind a tunnel interface
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TunnelKind {
+    WireGuard,
+    OpenVpn,
+    Tailscale,
+    ZeroTier,
+    Nebula,
+    AnyConnect,
+    GlobalProtect,
+    Ppp,
+    /// policy based IPsec without a tunnel interface
+    Ipsec,
+    /// unencrypted GRE, IPIP or SIT tunnel
+    IpTunnel,
+    /// name assigned by the user configuration
+    Custom(String),
+    Unknown,
+}
+
+impl Display for TunnelKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::WireGuard => write!(f, "wireguard"),
+            Self::OpenVpn => write!(f, "openvpn"),
+            Self::Tailscale => write!(f, "tailscale"),
+            Self::ZeroTier => write!(f, "zerotier"),
+            Self::Nebula => write!(f, "nebula"),
+            Self::AnyConnect => write!(f, "anyconnect"),
+            Self::GlobalProtect => write!(f, "globalprotect"),
+            Self::Ppp => write!(f, "ppp"),
+            Self::Ipsec => write!(f, "ipsec"),
+            Self::IpTunnel => write!(f, "ip tunnel"),
+            Self::Custom(ref name) => write!(f, "{}", name),
+            Self::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+impl TunnelKind {
+    /// Check whether the kind is a mesh overlay network connecting peers rather than
+    /// tunneling traffic to the internet
+    pub fn is_mesh(&self) -> bool {
+        matches!(self, Self::Tailscale | Self::ZeroTier | Self::Nebula)
+    }
+}
+
+impl From<&str> for TunnelKind {
+    /// Any name besides the known kinds is a custom kind
+    fn from(str: &str) -> Self {
+        match str.trim().to_lowercase().as_str() {
+            "wireguard" => Self::WireGuard,
+            "openvpn" => Self::OpenVpn,
+            "tailscale" => Self::Tailscale,
+            "zerotier" => Self::ZeroTier,
+            "nebula" => Self::Nebula,
+            "anyconnect" | "openconnect" => Self::AnyConnect,
+            "globalprotect" => Self::GlobalProtect,
+            "ppp" | "l2tp" | "pptp" | "sstp" => Self::Ppp,
+            "ipsec" | "strongswan" | "libreswan" => Self::Ipsec,
+            "ip tunnel" | "gre" | "ipip" | "sit" => Self::IpTunnel,
+            "unknown" => Self::Unknown,
+            _ => Self::Custom(str.to_string()),
+        }
+    }
+}
+
+/// Tunnel interface and the kind of VPN behind it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tunnel {
+    pub name: String,
+    pub kind: TunnelKind,
+    /// whether the tunnel is a mesh overlay routing only its own private ranges
+    pub mesh: bool,
+}
+
+/// Link layer details of an interface, as exposed in `/sys/class/net/<interface>`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkInfo {
+    /// ARPHRD hardware type
+    pub hardware_type: Option<u16>,
+    /// device type reported by the driver, e.g. `wireguard`
+    pub device_type: Option<String>,
+    /// whether the interface is created by the tun/tap driver
+    pub tun_driver: bool,
+}
+
+/// Read the link details of an interface from a sysfs `class/net` directory, e.g. `/sys/class/net`
+pub fn read_link_info(class_net: &Path, name: &str) -> Option<LinkInfo> {
+    let path = class_net.join(name);
+    if !path.is_dir() {
+        return None;
+    }
+    let hardware_type = std::fs::read_to_string(path.join("type"))
+        .ok()
+        .and_then(|content| content.trim().parse().ok());
+    let device_type = std::fs::read_to_string(path.join("uevent"))
+        .ok()
+        .and_then(|content| {
+            content
+                .lines()
+                .find_map(|line| line.strip_prefix("DEVTYPE="))
+                .map(str::to_string)
+        });
+    Some(LinkInfo {
+        hardware_type,
+        device_type,
+        tun_driver: path.join("tun_flags").exists(),
+    })
+}
+
+/// Check whether an interface is a PPP link, like the ones of L2TP, PPTP and SSTP connections
+pub fn is_ppp(name: &str, link: Option<&LinkInfo>) -> bool {
+    match link.and_then(|link| link.hardware_type) {
+        Some(hardware_type) => hardware_type == ARPHRD_PPP,
+        None => glob::matches("ppp*", name),
+    }
+}
+
+/// Check whether an interface is a GRE, IPIP or SIT tunnel, which carry traffic unencrypted
+pub fn is_ip_tunnel(name: &str, link: Option<&LinkInfo>) -> bool {
+    match link.and_then(|link| link.hardware_type) {
+        Some(hardware_type) => ARPHRD_IP_TUNNELS.contains(&hardware_type),
+        None => IP_TUNNEL_NAMES
+            .iter()
+            .any(|pattern| glob::matches(pattern, name)),
+    }
+}
+
+/// Well known interface names, checked in order
+const NAME_PATTERNS: &[(&str, TunnelKind)] = &[
+    ("tailscale*", TunnelKind::Tailscale),
+    ("zt*", TunnelKind::ZeroTier),
+    ("nebula*", TunnelKind::Nebula),
+    ("cscotun*", TunnelKind::AnyConnect),
+    ("gpd*", TunnelKind::GlobalProtect),
+    ("nordlynx", TunnelKind::WireGuard),
+    ("proton*", TunnelKind::WireGuard),
+    ("*mullvad*", TunnelKind::WireGuard),
+    ("wg*", TunnelKind::WireGuard),
+];
+
+/// Identify the kind of VPN behind a tunnel interface.
+///
+/// User mappings of interface name patterns take precedence, followed by well known
+/// interface names and the link details reported by the kernel.
+pub fn classify(
+    name: &str,
+    link: Option<&LinkInfo>,
+    mappings: &HashMap<String, String>,
+) -> TunnelKind {
+    // prefer the most specific user pattern
+    let mut patterns: Vec<(&String, &String)> = mappings.iter().collect();
+    patterns.sort_by_key(|(pattern, _)| std::cmp::Reverse(pattern.len()));
+    if let Some((_, kind)) = patterns
+        .into_iter()
+        .find(|(pattern, _)| glob::matches(pattern, name))
+    {
+        return TunnelKind::from(kind.as_str());
+    }
+
+    if let Some((_, kind)) = NAME_PATTERNS
+        .iter()
+        .find(|(pattern, _)| glob::matches(pattern, name))
+    {
+        return kind.clone();
+    }
+
+    if let Some(link) = link {
+        if link.device_type.as_deref() == Some("wireguard") {
+            return TunnelKind::WireGuard;
+        }
+        if link.hardware_type == Some(ARPHRD_PPP) {
+            return TunnelKind::Ppp;
+        }
+        // userspace VPNs with other names are recognized by their name above
+        if link.tun_driver {
+            return TunnelKind::OpenVpn;
+        }
+    }
+
+    // tunl0 and the like would be taken for tun devices
+    if is_ip_tunnel(name, link) {
+        TunnelKind::IpTunnel
+    } else if glob::matches("ppp*", name) {
+        TunnelKind::Ppp
+    } else if glob::matches("tun*", name) || glob::matches("tap*", name) {
+        TunnelKind::OpenVpn
+    } else {
+        TunnelKind::Unknown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kind(name: &str) -> TunnelKind {
+        classify(name, None, &HashMap::new())
+    }
+
+    #[test]
+    fn well_known_names() {
+        assert_eq!(kind("wg0"), TunnelKind::WireGuard);
+        assert_eq!(kind("nordlynx"), TunnelKind::WireGuard);
+        assert_eq!(kind("proton0"), TunnelKind::WireGuard);
+        assert_eq!(kind("wg0-mullvad"), TunnelKind::WireGuard);
+        assert_eq!(kind("tun0"), TunnelKind::OpenVpn);
+        assert_eq!(kind("tailscale0"), TunnelKind::Tailscale);
+        assert_eq!(kind("ztabcdef12"), TunnelKind::ZeroTier);
+        assert_eq!(kind("nebula1"), TunnelKind::Nebula);
+        assert_eq!(kind("cscotun0"), TunnelKind::AnyConnect);
+        assert_eq!(kind("gpd0"), TunnelKind::GlobalProtect);
+        assert_eq!(kind("ppp0"), TunnelKind::Ppp);
+        assert_eq!(kind("utun3"), TunnelKind::Unknown);
+        assert_eq!(kind("gre1"), TunnelKind::IpTunnel);
+        assert_eq!(kind("tunl0"), TunnelKind::IpTunnel);
+        assert_eq!(kind("sit0"), TunnelKind::IpTunnel);
+    }
+
+    #[test]
+    fn point_to_point_types() {
+        let link = |hardware_type| LinkInfo {
+            hardware_type: Some(hardware_type),
+            ..Default::default()
+        };
+        assert!(is_ppp("ppp0", None));
+        assert!(is_ppp("sstp-work", Some(&link(ARPHRD_PPP))));
+        assert!(!is_ppp("ppp0", Some(&link(65534))));
+
+        assert!(is_ip_tunnel("tunl0", None));
+        assert!(is_ip_tunnel("to-office", Some(&link(778))));
+        assert!(!is_ip_tunnel("tun0", None));
+        assert!(!is_ip_tunnel("gre-vpn", Some(&link(65534))));
+    }
+
+    #[test]
+    fn link_details() {
+        let wireguard = LinkInfo {
+            hardware_type: Some(65534),
+            device_type: Some("wireguard".to_string()),
+            tun_driver: false,
+        };
+        assert_eq!(
+            classify("work", Some(&wireguard), &HashMap::new()),
+            TunnelKind::WireGuard
+        );
+
+        let ppp = LinkInfo {
+            hardware_type: Some(ARPHRD_PPP),
+            ..Default::default()
+        };
+        assert_eq!(
+            classify("l2tp-office", Some(&ppp), &HashMap::new()),
+            TunnelKind::Ppp
+        );
+        let tun = LinkInfo {
+            hardware_type: Some(65534),
+            tun_driver: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            classify("vpn-office", Some(&tun), &HashMap::new()),
+            TunnelKind::OpenVpn
+        );
+        assert_eq!(
+            classify("vpn-office", None, &HashMap::new()),
+            TunnelKind::Unknown
+        );
+    }
+
+    #[test]
+    fn user_mappings() {
+        let mappings = HashMap::from([
+            ("tun*".to_string(), "openconnect".to_string()),
+            ("tun-corp*".to_string(), "Corp VPN".to_string()),
+        ]);
+        assert_eq!(classify("tun0", None, &mappings), TunnelKind::AnyConnect);
+        assert_eq!(
+            classify("tun-corp", None, &mappings),
+            TunnelKind::Custom("Corp VPN".to_string())
+        );
+        assert_eq!(classify("wg0", None, &mappings), TunnelKind::WireGuard);
+        assert_eq!(
+            TunnelKind::Custom("Corp VPN".to_string()).to_string(),
+            "Corp VPN"
+        );
+    }
+
+    #[test]
+    fn sysfs() {
+        let class_net = Path::new("fixtures/sys/class/net");
+        let wg = read_link_info(class_net, "wg-work").unwrap();
+        assert_eq!(wg.hardware_type, Some(65534));
+        assert_eq!(wg.device_type.as_deref(), Some("wireguard"));
+        assert!(!wg.tun_driver);
+
+        let tun = read_link_info(class_net, "tun0").unwrap();
+        assert_eq!(tun.device_type, None);
+        assert!(tun.tun_driver);
+
+        let ppp = read_link_info(class_net, "ppp0").unwrap();
+        assert_eq!(ppp.hardware_type, Some(ARPHRD_PPP));
+
+        assert!(read_link_info(class_net, "missing0").is_none());
+    }
+}
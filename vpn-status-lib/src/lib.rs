@@ -1,7 +1,8 @@
 //! # VPN Status
 //!
 //! A simple library to determine whether VPN is enabled.
-//! This is performed by checking if the default interface is a tun device.
+//! This is performed by checking if the default interface is a tun device,
+//! or if tun devices or IPsec policies cover the whole public address space.
 //!
 //! ## Usage
 //! ```toml
@@ -19,25 +20,69 @@
 //! }
 //! ```
 
+pub mod chain;
 pub mod config;
+pub mod connectivity;
+pub mod container;
+pub mod dns;
 pub mod error;
+mod glob;
+pub mod ipsec;
+pub mod kind;
+pub mod namespace;
+#[cfg(target_os = "linux")]
+mod netlink;
+pub mod networkmanager;
 pub mod parser;
+pub mod policy;
+pub mod process;
+pub mod report;
+pub mod routes;
+pub mod rules;
+pub mod source;
+pub mod state;
 pub mod styles;
+pub mod traffic;
+pub mod trust;
+pub mod wireguard;
 
-use config::Config;
-use error::VpnStatusError;
+use config::{Config, StyleConfig};
+use dns::Nameserver;
+use error::{VpnStatusError, Warning};
+use kind::Tunnel;
 use log::debug;
+use namespace::Namespace;
 use public_ip_address::lookup::LookupProvider;
+use routes::{AddressFamily, RouteMatch};
+use source::{FilteredSource, InterfaceSource, NetdevSource};
 use std::collections::HashMap;
 use std::fmt::Display;
 use std::net::IpAddr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 /// VPN configuration status
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum VpnStatus {
     Disabled,
     Enabled,
     SplitTunnel,
+    /// only mesh overlay networks are connected, e.g. Tailscale without an exit node
+    Mesh,
+    /// a tunnel transmits without receiving anything, e.g. when the VPN daemon died
+    Stalled,
+    /// a VPN client runs, but its tunnel is not up yet
+    Connecting,
+    /// the default route is tunneled through fewer nested tunnels than `min_hops`
+    ShortChain,
+    /// destinations are routed against `must_tunnel` or `must_bypass`
+    Violation,
+    /// no tunnel is up, on a network listed in `trusted_networks`
+    Trusted,
+    /// a captive portal intercepts the traffic until the user logs in
+    CaptivePortal,
+    /// the link is up, but there is no default route
+    NoGateway,
+    /// no interface is up
     Offline,
 }
 
@@ -47,6 +92,14 @@ impl Display for VpnStatus {
             Self::Disabled => write!(f, "disabled"),
             Self::Enabled => write!(f, "enabled"),
             Self::SplitTunnel => write!(f, "split"),
+            Self::Mesh => write!(f, "mesh"),
+            Self::Stalled => write!(f, "stalled"),
+            Self::Connecting => write!(f, "connecting"),
+            Self::ShortChain => write!(f, "short chain"),
+            Self::Violation => write!(f, "violation"),
+            Self::Trusted => write!(f, "trusted"),
+            Self::CaptivePortal => write!(f, "captive portal"),
+            Self::NoGateway => write!(f, "no gateway"),
             Self::Offline => write!(f, "offline"),
         }
     }
@@ -68,90 +121,777 @@ impl Display for VpnStatus {
 /// # }
 /// ```
 pub fn status() -> Result<VpnStatus, VpnStatusError> {
-    match netdev::get_default_interface() {
-        Ok(interface) => {
-            if interface.is_tun() {
-                Ok(VpnStatus::Enabled)
-            } else {
-                // check for split tunnel get_interfaces
-                for address in all_tunnel_addresses()
-                    .values()
-                    .flat_map(|vec| vec.iter())
-                    .collect::<Vec<&IpAddr>>()
-                {
-                    if address.is_ipv4() {
-                        return Ok(VpnStatus::SplitTunnel);
+    status_with(&NetdevSource)
+}
+
+/// Get the status of the VPN configuration from the given interface source.
+///
+/// # Example
+/// ```rust
+/// # use vpn_status_lib::source::MemorySource;
+/// # use vpn_status_lib::VpnStatus;
+/// # use vpn_status_lib::error::VpnStatusError;
+/// let source = MemorySource::default();
+/// match vpn_status_lib::status_with(&source) {
+///     Ok(status) => println!("VPN is {}", status),
+///     Err(VpnStatusError::NoInterfaces) => println!("no network interfaces"),
+///     Err(error) => println!("failed getting the status: {}", error),
+/// }
+/// ```
+pub fn status_with(source: &dyn InterfaceSource) -> Result<VpnStatus, VpnStatusError> {
+    detect_with(source, &Config::default()).map(|detection| detection.status)
+}
+
+/// Get the status of the VPN configuration in a network namespace, e.g. of a container.
+///
+/// # Example
+/// ```rust,no_run
+/// # use vpn_status_lib::namespace::Namespace;
+/// let status = vpn_status_lib::status_in(&Namespace::Pid(4242)).unwrap();
+/// println!("container VPN is {}", status);
+/// ```
+pub fn status_in(namespace: &Namespace) -> Result<VpnStatus, VpnStatusError> {
+    status_with(&namespace.capture()?)
+}
+
+/// Get the status of a single tunnel interface, ignoring all other tunnels.
+///
+/// The status is enabled when the tunnel carries the default route, split when it only carries
+/// some routes, and disabled when it is down.
+///
+/// # Example
+/// ```rust
+/// # use vpn_status_lib::error::VpnStatusError;
+/// match vpn_status_lib::status_for_interface("wg-work") {
+///     Ok(status) => println!("wg-work is {status}"),
+///     Err(VpnStatusError::InterfaceNotFound(name)) => println!("{name} does not exist"),
+///     Err(error) => println!("{error}"),
+/// }
+/// ```
+pub fn status_for_interface(name: &str) -> Result<VpnStatus, VpnStatusError> {
+    status_for_interface_with(&NetdevSource, name)
+}
+
+/// Get the status of a single tunnel interface from the given interface source.
+pub fn status_for_interface_with(
+    source: &dyn InterfaceSource,
+    name: &str,
+) -> Result<VpnStatus, VpnStatusError> {
+    let config = Config {
+        interface: Some(name.to_string()),
+        ..Default::default()
+    };
+    detect_with(source, &config).map(|detection| detection.status)
+}
+
+/// Result of the VPN detection
+#[derive(Debug, Clone, PartialEq)]
+pub struct Detection {
+    /// status of the VPN configuration
+    pub status: VpnStatus,
+    /// IPv4 default route and the policy rule which selected it
+    pub route: Option<RouteMatch>,
+    /// address family bypassing the tunnel while the other one is tunneled
+    pub leak: Option<AddressFamily>,
+    /// active nameservers
+    pub nameservers: Vec<Nameserver>,
+    /// nameservers reached outside of the tunnel while it is active
+    pub dns_leak: Vec<Nameserver>,
+    /// tunnel interfaces and the kind of VPN behind them
+    pub tunnels: Vec<Tunnel>,
+    /// outbound IPsec policies sending traffic through an established tunnel
+    pub ipsec: Vec<ipsec::Policy>,
+    /// traffic of the tunnels since the previous run
+    pub traffic: HashMap<String, traffic::Traffic>,
+    /// peers of the WireGuard tunnels
+    pub wireguard: HashMap<String, Vec<wireguard::Peer>>,
+    /// time of the detection in seconds since the unix epoch
+    pub timestamp: u64,
+    /// time the VPN connected in seconds since the unix epoch, as observed by previous runs
+    pub connected_since: Option<u64>,
+    /// destinations routed against `must_tunnel` or `must_bypass`
+    pub violations: Vec<policy::Violation>,
+    /// running VPN client processes
+    pub clients: Vec<process::Client>,
+    /// VPN connections managed by NetworkManager
+    pub connections: Vec<networkmanager::Connection>,
+    /// tunnels nested in the tunnel carrying the default route, and the interface below them
+    pub chain: Vec<chain::Hop>,
+    /// pattern of `trusted_networks` matching the network, while no tunnel is up
+    pub trusted: Option<String>,
+    /// errors of the backends which could not be read, e.g. without `CAP_NET_ADMIN`
+    pub warnings: Vec<Warning>,
+}
+
+impl Detection {
+    fn new(status: VpnStatus) -> Self {
+        Self {
+            status,
+            route: None,
+            leak: None,
+            nameservers: vec![],
+            dns_leak: vec![],
+            tunnels: vec![],
+            ipsec: vec![],
+            traffic: HashMap::new(),
+            wireguard: HashMap::new(),
+            timestamp: now(),
+            connected_since: None,
+            violations: vec![],
+            clients: vec![],
+            connections: vec![],
+            chain: vec![],
+            trusted: None,
+            warnings: vec![],
+        }
+    }
+
+    /// Get the time since the VPN connected
+    pub fn uptime(&self) -> Option<Duration> {
+        self.connected_since
+            .map(|since| Duration::from_secs(self.timestamp.saturating_sub(since)))
+    }
+
+    /// Get the tunnel carrying the default route, if any
+    pub fn active_tunnel(&self) -> Option<&Tunnel> {
+        let route = self.route.as_ref()?;
+        self.tunnels
+            .iter()
+            .find(|tunnel| tunnel.name == route.route.interface)
+    }
+}
+
+/// Detect the VPN configuration, including the routing decision behind the status.
+///
+/// # Example
+/// ```rust
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let detection = vpn_status_lib::detect()?;
+/// if let Some(route) = detection.route {
+///     println!("default route via {} in table {}", route.route.interface, route.route.table);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn detect() -> Result<Detection, VpnStatusError> {
+    detect_with(&NetdevSource, &Config::default())
+}
+
+/// Detect the VPN configuration from the given interface source.
+///
+/// Interfaces excluded by the configuration are not considered tunnels.
+///
+/// The host is offline when no interface besides loopback is up with an address, regardless of
+/// the default route.
+///
+/// Backends which can not be read, like the WireGuard peers without `CAP_NET_ADMIN`, do not fail
+/// the detection. Their errors are listed in [`Detection::warnings`].
+pub fn detect_with(
+    source: &dyn InterfaceSource,
+    config: &Config,
+) -> Result<Detection, VpnStatusError> {
+    let interfaces = source.interfaces();
+    if interfaces.is_empty() {
+        return Err(VpnStatusError::NoInterfaces);
+    }
+    if let Some(ref name) = config.interface {
+        if !interfaces.iter().any(|interface| &interface.name == name) {
+            return Err(VpnStatusError::InterfaceNotFound(name.clone()));
+        }
+    }
+    if !connectivity::has_link(&interfaces) {
+        return Ok(Detection::new(VpnStatus::Offline));
+    }
+    let source = &FilteredSource::from_config(source, config);
+    let interface = match source.default_interface() {
+        Ok(interface) => interface,
+        Err(error) => {
+            debug!("no default interface: {}", error);
+            return Ok(Detection::new(VpnStatus::NoGateway));
+        }
+    };
+
+    // backends which can not be read are reported, the detection continues without them
+    let mut warnings = vec![];
+    let routes = read_or_warn(source.try_routes(), || source.routes(), &mut warnings);
+    let rules = read_or_warn(source.try_rules(), || source.rules(), &mut warnings);
+    let tunnels = tunnel_names(source);
+    let route = routes::default_route(&routes, &rules, AddressFamily::Ipv4);
+
+    // policy based IPsec encrypts traffic matching its selectors without a tunnel interface
+    let local = local_addresses(source);
+    let policies = read_or_warn(source.try_ipsec_policies(), Vec::new, &mut warnings);
+    let states = read_or_warn(source.try_ipsec_states(), Vec::new, &mut warnings);
+    let ipsec_covers = |family| ipsec::covers_public_space(&policies, &states, &local, family);
+    let (ipsec_ipv4, ipsec_ipv6) = (
+        ipsec_covers(AddressFamily::Ipv4),
+        ipsec_covers(AddressFamily::Ipv6),
+    );
+
+    // the tunnel might override the default route with more specific routes or policy rules
+    let ipv4_tunneled = interface.is_tun()
+        || routes::covers_public_space(&routes, &rules, &tunnels, AddressFamily::Ipv4)
+        || ipsec_ipv4;
+    let ipv6_tunneled =
+        routes::covers_public_space(&routes, &rules, &tunnels, AddressFamily::Ipv6) || ipsec_ipv6;
+
+    // one address family is tunneled while the other one still reaches the internet directly
+    let leak = if ipv4_tunneled
+        && !ipsec_ipv6
+        && routes::bypasses_interfaces(&routes, &rules, &tunnels, AddressFamily::Ipv6)
+    {
+        Some(AddressFamily::Ipv6)
+    } else if ipv6_tunneled
+        && !ipsec_ipv4
+        && routes::bypasses_interfaces(&routes, &rules, &tunnels, AddressFamily::Ipv4)
+    {
+        Some(AddressFamily::Ipv4)
+    } else {
+        None
+    };
+    if let Some(family) = leak {
+        debug!("{} traffic bypasses the tunnel", family);
+    }
+
+    // overlay networks which only route their own ranges do not tunnel any traffic
+    let addresses = tunnel_addresses(source);
+    let mappings = config.providers.clone().unwrap_or_default();
+    let clients = source.clients();
+    let connections = source.connections();
+    let tunnels: Vec<Tunnel> = tunnels
+        .into_iter()
+        .map(|name| {
+            // the client running the tunnel is more reliable than its name
+            let client = clients
+                .iter()
+                .find(|client| client.interface.as_ref() == Some(&name));
+            let kind = match client {
+                Some(client) if !mappings.keys().any(|pattern| glob::matches(pattern, &name)) => {
+                    client.kind.clone()
+                }
+                _ => kind::classify(&name, source.link_info(&name).as_ref(), &mappings),
+            };
+            let overlay_addresses = addresses.get(&name).is_some_and(|addresses| {
+                let addresses: Vec<&IpAddr> = addresses
+                    .iter()
+                    .filter(|address| !is_link_local(address))
+                    .collect();
+                !addresses.is_empty()
+                    && addresses
+                        .iter()
+                        .all(|address| routes::is_overlay_address(address))
+            });
+            let mesh = (kind.is_mesh() || overlay_addresses)
+                && !routes::routes_public_destinations(&routes, &name);
+            Tunnel { name, kind, mesh }
+        })
+        .collect();
+
+    let ipsec = ipsec::active_policies(&policies, &states);
+    if !ipsec.is_empty() {
+        debug!("active ipsec policies: {:?}", ipsec);
+    }
+
+    let status = if ipv4_tunneled {
+        VpnStatus::Enabled
+    } else if !ipsec.is_empty()
+        || tunnels.iter().any(|tunnel| {
+            !tunnel.mesh
+                && addresses
+                    .get(&tunnel.name)
+                    .is_some_and(|addresses| addresses.iter().any(|address| address.is_ipv4()))
+        })
+    {
+        // check for split tunnel
+        VpnStatus::SplitTunnel
+    } else if tunnels.iter().any(|tunnel| tunnel.mesh) {
+        VpnStatus::Mesh
+    } else if clients
+        .iter()
+        .any(|client| !client.daemon && !client.kind.is_mesh())
+        || connections
+            .iter()
+            .any(|connection| connection.state == networkmanager::ConnectionState::Activating)
+    {
+        // the client is still negotiating the tunnel
+        debug!(
+            "vpn clients without a tunnel: {:?} {:?}",
+            clients, connections
+        );
+        VpnStatus::Connecting
+    } else {
+        // no tunnels found
+        VpnStatus::Disabled
+    };
+    // the tunnel exists but the VPN daemon stopped answering
+    let counters: Vec<(String, traffic::Counters)> = tunnels
+        .iter()
+        .filter_map(|tunnel| Some((tunnel.name.clone(), source.counters(&tunnel.name)?)))
+        .collect();
+    let clock = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let timestamp = clock.as_secs();
+    // a single interface check keeps its own state, apart from the one of all tunnels
+    let state_path = config
+        .state_path
+        .clone()
+        .or_else(|| source.state_path())
+        .map(|path| match config.interface {
+            Some(ref interface) => state::interface_state_path(&path, interface),
+            None => path,
+        });
+    let mut state = state_path
+        .as_deref()
+        .map(state::State::load)
+        .unwrap_or_default();
+    let stall_timeout = config.stall_timeout.unwrap_or(60) as f64;
+    let traffic = state
+        .traffic
+        .sample(&counters, clock.as_secs_f64(), stall_timeout);
+
+    // the WireGuard peers stopped renewing the session
+    let wireguard: HashMap<String, Vec<wireguard::Peer>> = tunnels
+        .iter()
+        .filter(|tunnel| tunnel.kind == kind::TunnelKind::WireGuard)
+        .map(|tunnel| {
+            let peers = source.try_wireguard_peers(&tunnel.name);
+            (
+                tunnel.name.clone(),
+                read_or_warn(peers, Vec::new, &mut warnings),
+            )
+        })
+        .collect();
+    let handshake_timeout = Duration::from_secs(config.handshake_timeout.unwrap_or(180));
+    let stalled = tunnels.iter().any(|tunnel| {
+        !tunnel.mesh
+            && (traffic
+                .get(&tunnel.name)
+                .is_some_and(|traffic| traffic.stalled)
+                || wireguard.get(&tunnel.name).is_some_and(|peers| {
+                    wireguard::handshake_expired(peers, timestamp, handshake_timeout)
+                }))
+    });
+    let status = match status {
+        VpnStatus::Enabled | VpnStatus::SplitTunnel if stalled => {
+            debug!("tunnel stalled: {:?} {:?}", traffic, wireguard);
+            VpnStatus::Stalled
+        }
+        status => status,
+    };
+
+    // tunnels nested in each other, found through the endpoint of each one
+    let names: Vec<String> = tunnels.iter().map(|tunnel| tunnel.name.clone()).collect();
+    let chain = match route {
+        Some(ref route) if names.contains(&route.route.interface) => {
+            let endpoints: HashMap<String, IpAddr> = wireguard
+                .iter()
+                .filter_map(|(name, peers)| {
+                    let peer = wireguard::latest_peer(peers).or(peers.first())?;
+                    Some((name.clone(), peer.endpoint?.ip()))
+                })
+                .collect();
+            chain::resolve(&route.route.interface, &names, &endpoints, &routes, &rules)
+        }
+        _ => vec![],
+    };
+    let status = match (status, config.min_hops) {
+        (VpnStatus::Enabled, Some(min_hops)) if chain::hops(&chain) < min_hops => {
+            debug!("tunnel chain too short: {}", chain::format(&chain));
+            VpnStatus::ShortChain
+        }
+        (status, _) => status,
+    };
+
+    // no VPN is needed on known home or office networks
+    let trusted = match (status, &config.trusted_networks) {
+        (VpnStatus::Disabled, Some(patterns)) => {
+            let network =
+                trust::Network::identify(source, &interface, route.as_ref().map(|r| &r.route));
+            debug!("network: {:?}", network);
+            trust::trusted(patterns, &network).cloned()
+        }
+        _ => None,
+    };
+    let status = if trusted.is_some() {
+        VpnStatus::Trusted
+    } else {
+        status
+    };
+
+    // the tunnel can not come up until the user logs in to the network
+    let status = match (status, &config.captive_portal_url) {
+        (VpnStatus::Disabled | VpnStatus::Connecting | VpnStatus::Stalled, Some(url)) => {
+            match connectivity::captive_portal(url, connectivity::PROBE_TIMEOUT) {
+                Ok(true) => VpnStatus::CaptivePortal,
+                Ok(false) => status,
+                Err(error) => {
+                    debug!("captive portal probe failed: {}", error);
+                    status
+                }
+            }
+        }
+        _ => status,
+    };
+
+    // remember when the connection was established
+    let connected = matches!(
+        status,
+        VpnStatus::Enabled | VpnStatus::SplitTunnel | VpnStatus::Stalled | VpnStatus::ShortChain
+    );
+    state.update_connection(connected, timestamp);
+    if let Some(path) = state_path {
+        if let Err(error) = state.save(&path) {
+            debug!("failed saving state to {}: {}", path.display(), error);
+        }
+    }
+
+    let nameservers = source.nameservers();
+    let dns_leak = match status {
+        VpnStatus::Enabled | VpnStatus::SplitTunnel | VpnStatus::ShortChain => {
+            let mut leaking = dns::leaking(&nameservers, &routes, &rules, &names);
+            leaking.retain(|nameserver| {
+                !ipsec::protects(&policies, &states, &local, nameserver.address)
+            });
+            leaking
+        }
+        _ => vec![],
+    };
+    if !dns_leak.is_empty() {
+        debug!("nameservers outside of the tunnel: {:?}", dns_leak);
+    }
+
+    // destinations routed against the tunnel policy, only while any tunnel is up, mesh overlays
+    // only route their own ranges
+    let mut violations = vec![];
+    if !matches!(
+        status,
+        VpnStatus::Disabled
+            | VpnStatus::Connecting
+            | VpnStatus::Mesh
+            | VpnStatus::Trusted
+            | VpnStatus::CaptivePortal
+    ) {
+        let requirements = [
+            (policy::Requirement::Tunnel, &config.must_tunnel),
+            (policy::Requirement::Bypass, &config.must_bypass),
+        ];
+        for (requirement, destinations) in requirements {
+            for destination in destinations.iter().flatten() {
+                for address in policy::addresses(destination, &routes, &rules) {
+                    let egress =
+                        egress(address, &routes, &rules, &names, &local, &policies, &states);
+                    if egress.tunneled() != (requirement == policy::Requirement::Tunnel) {
+                        violations.push(policy::Violation {
+                            destination: destination.clone(),
+                            address,
+                            requirement,
+                            interface: egress.interface().map(str::to_string),
+                        });
                     }
                 }
-                // no tunnels found
-                Ok(VpnStatus::Disabled)
             }
         }
-        Err(error) => match error.as_str() {
-            "Local IP address not found" => Ok(VpnStatus::Offline),
-            _ => Err(VpnStatusError::DefaultInterface(error)),
-        },
     }
+    let status = if violations.is_empty() {
+        status
+    } else {
+        debug!("tunnel policy violations: {:?}", violations);
+        VpnStatus::Violation
+    };
+
+    Ok(Detection {
+        status,
+        route,
+        leak,
+        nameservers,
+        dns_leak,
+        tunnels,
+        ipsec,
+        traffic,
+        wireguard,
+        timestamp,
+        connected_since: state.connected_since,
+        violations,
+        clients,
+        connections,
+        chain,
+        trusted,
+        warnings,
+    })
 }
 
-/// Get the name of the default tunnel.
-pub fn tunnel_name() -> Result<String, VpnStatusError> {
-    match netdev::get_default_interface() {
-        Ok(interface) => {
-            if interface.is_tun() {
-                Ok(interface.name)
-            } else {
-                Err(VpnStatusError::DefaultInterface(
-                    "Default interface is not a tunnel".to_string(),
-                ))
+/// Get the result of reading a backend, or the fallback while remembering the error
+fn read_or_warn<T>(
+    result: Result<T, VpnStatusError>,
+    fallback: impl FnOnce() -> T,
+    warnings: &mut Vec<Warning>,
+) -> T {
+    result.unwrap_or_else(|error| {
+        debug!("{}", error);
+        warnings.push(error.into());
+        fallback()
+    })
+}
+
+/// Detect the VPN configuration and explain the status with the signals behind it.
+///
+/// # Example
+/// ```rust
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let report = vpn_status_lib::report()?;
+/// println!("{} ({:.0}% confidence)", report.status, report.confidence * 100.0);
+/// for signal in report.signals {
+///     println!("{}: {} ({})", signal.signal, signal.verdict, signal.detail);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn report() -> Result<report::StatusReport, VpnStatusError> {
+    report_with(&NetdevSource, &Config::default())
+}
+
+/// Detect the VPN configuration from the given interface source and explain the status.
+///
+/// The public IP address is looked up only when `lookup` is enabled and `exit_ranges` are set.
+pub fn report_with(
+    source: &dyn InterfaceSource,
+    config: &Config,
+) -> Result<report::StatusReport, VpnStatusError> {
+    let detection = detect_with(source, config)?;
+    let exit_ranges: Vec<netdev::ipnet::IpNet> = config
+        .exit_ranges
+        .iter()
+        .flatten()
+        .filter_map(|range| match range.parse() {
+            Ok(range) => Some(range),
+            Err(error) => {
+                debug!("invalid exit range {}: {}", range, error);
+                None
+            }
+        })
+        .collect();
+    let public_ip = if !matches!(
+        detection.status,
+        VpnStatus::Offline | VpnStatus::NoGateway | VpnStatus::CaptivePortal
+    ) && config.lookup.unwrap_or(false)
+        && !exit_ranges.is_empty()
+    {
+        match lookup_public_ip(&config.lookup_providers) {
+            Ok(response) => Some(response.ip),
+            Err(error) => {
+                debug!("public ip lookup failed: {}", error);
+                None
             }
         }
+    } else {
+        None
+    };
+    let routes = source.routes();
+    Ok(report::StatusReport::new(
+        detection,
+        &routes,
+        public_ip,
+        &exit_ranges,
+    ))
+}
+
+/// Egress of the traffic to a destination
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Egress {
+    pub destination: IpAddr,
+    /// route selected by the routing tables and policy rules, none if it is unreachable
+    pub route: Option<RouteMatch>,
+    /// whether the route egresses through one of the tunnels
+    pub tunnel: bool,
+    /// whether IPsec policies protect the traffic
+    pub ipsec: bool,
+}
+
+impl Egress {
+    /// Get the egress interface
+    pub fn interface(&self) -> Option<&str> {
+        self.route
+            .as_ref()
+            .map(|found| found.route.interface.as_str())
+    }
+
+    /// Get the gateway, none for directly connected destinations
+    pub fn gateway(&self) -> Option<IpAddr> {
+        self.route.as_ref().and_then(|found| found.route.gateway)
+    }
+
+    /// Check whether the traffic goes through the VPN
+    pub fn tunneled(&self) -> bool {
+        self.tunnel || self.ipsec
+    }
+}
+
+/// Find out how traffic to a destination leaves the host.
+///
+/// # Example
+/// ```rust
+/// let egress = vpn_status_lib::route_to("10.20.0.5".parse().unwrap());
+/// if egress.tunneled() {
+///     println!("10.20.0.5 is reached through the VPN");
+/// }
+/// ```
+pub fn route_to(destination: IpAddr) -> Egress {
+    let source = FilteredSource::from_config(&NetdevSource, &Config::default());
+    route_to_with(&source, destination)
+}
+
+/// Find out how traffic to a destination leaves the host from the given interface source.
+///
+/// The route is selected by longest prefix match over the routing tables chosen by the
+/// routing policy rules, like `ip route get`. Interfaces flagged as point-to-point by the
+/// source are tunnels, wrap it in a [`FilteredSource`] to apply the configuration.
+pub fn route_to_with(source: &dyn InterfaceSource, destination: IpAddr) -> Egress {
+    egress(
+        destination,
+        &source.routes(),
+        &source.rules(),
+        &tunnel_names(source),
+        &local_addresses(source),
+        &source.ipsec_policies(),
+        &source.ipsec_states(),
+    )
+}
+
+/// Find out how traffic to a destination leaves the host from the read routing state
+fn egress(
+    destination: IpAddr,
+    routes: &[routes::Route],
+    rules: &[rules::Rule],
+    tunnels: &[String],
+    local: &[IpAddr],
+    policies: &[ipsec::Policy],
+    states: &[ipsec::State],
+) -> Egress {
+    // local destinations are only listed in the local table, which is not read
+    let route = if destination.is_loopback() || local.contains(&destination) {
+        let mut route = routes::Route::new(destination.into(), "lo");
+        route.table = routes::RT_TABLE_LOCAL;
+        Some(RouteMatch { route, rule: None })
+    } else {
+        routes::lookup(routes, rules, destination)
+    };
+    let tunnel = route
+        .as_ref()
+        .is_some_and(|found| tunnels.contains(&found.route.interface));
+    let ipsec = ipsec::protects(policies, states, local, destination);
+    Egress {
+        destination,
+        route,
+        tunnel,
+        ipsec,
+    }
+}
+
+/// Get the default interface, telling apart hosts without any interface
+fn default_interface(
+    source: &dyn InterfaceSource,
+) -> Result<Result<netdev::Interface, String>, VpnStatusError> {
+    if source.interfaces().is_empty() {
+        return Err(VpnStatusError::NoInterfaces);
+    }
+    Ok(source.default_interface())
+}
+
+/// Get the default interface if it is a tunnel
+fn default_tunnel(source: &dyn InterfaceSource) -> Result<netdev::Interface, VpnStatusError> {
+    match default_interface(source)? {
+        Ok(interface) if interface.is_tun() => Ok(interface),
+        Ok(_) => Err(VpnStatusError::DefaultInterface(
+            "Default interface is not a tunnel".to_string(),
+        )),
         Err(error) => Err(VpnStatusError::DefaultInterface(error)),
     }
 }
 
+/// Get the name of the default tunnel.
+pub fn tunnel_name() -> Result<String, VpnStatusError> {
+    tunnel_name_with(&NetdevSource)
+}
+
+/// Get the name of the default tunnel from the given interface source.
+pub fn tunnel_name_with(source: &dyn InterfaceSource) -> Result<String, VpnStatusError> {
+    tunnel_name_with_config(source, &Config::default())
+}
+
+/// Get the name of the default tunnel from the given interface source, among the tunnels
+/// allowed by the configuration.
+pub fn tunnel_name_with_config(
+    source: &dyn InterfaceSource,
+    config: &Config,
+) -> Result<String, VpnStatusError> {
+    default_tunnel(&FilteredSource::from_config(source, config)).map(|interface| interface.name)
+}
+
 /// Get the ip addresses of the default tunnel.
 pub fn tunnel_address() -> Result<Vec<IpAddr>, VpnStatusError> {
+    tunnel_address_with(&NetdevSource)
+}
+
+/// Get the ip addresses of the default tunnel from the given interface source.
+pub fn tunnel_address_with(source: &dyn InterfaceSource) -> Result<Vec<IpAddr>, VpnStatusError> {
+    tunnel_address_with_config(source, &Config::default())
+}
+
+/// Get the ip addresses of the default tunnel from the given interface source, among the
+/// tunnels allowed by the configuration.
+pub fn tunnel_address_with_config(
+    source: &dyn InterfaceSource,
+    config: &Config,
+) -> Result<Vec<IpAddr>, VpnStatusError> {
+    let interface = default_tunnel(&FilteredSource::from_config(source, config))?;
     let mut tunnel_addresses = vec![];
-    match netdev::get_default_interface() {
-        Ok(interface) => {
-            if interface.is_tun() {
-                if !interface.ipv4.is_empty() {
-                    for address in interface.ipv4 {
-                        let address = address.addr().to_string();
-                        if let Ok(address) = address.parse() {
-                            tunnel_addresses.push(address);
-                        }
-                    }
-                } else if !interface.ipv6.is_empty() {
-                    for address in interface.ipv6 {
-                        let address = address.addr().to_string();
-                        if let Ok(address) = address.parse() {
-                            tunnel_addresses.push(address);
-                        }
-                    }
-                } else {
-                    return Err(VpnStatusError::DefaultInterface(
-                        "Tunnel has no address".to_string(),
-                    ));
-                }
-            } else {
-                return Err(VpnStatusError::DefaultInterface(
-                    "Default interface is not a tunnel".to_string(),
-                ));
+    if !interface.ipv4.is_empty() {
+        for address in interface.ipv4 {
+            let address = address.addr().to_string();
+            if let Ok(address) = address.parse() {
+                tunnel_addresses.push(address);
             }
         }
-        Err(error) => {
-            return Err(VpnStatusError::DefaultInterface(error));
+    } else if !interface.ipv6.is_empty() {
+        for address in interface.ipv6 {
+            let address = address.addr().to_string();
+            if let Ok(address) = address.parse() {
+                tunnel_addresses.push(address);
+            }
         }
+    } else {
+        return Err(VpnStatusError::DefaultInterface(
+            "Tunnel has no address".to_string(),
+        ));
     }
     Ok(tunnel_addresses)
 }
 
 /// Get the names of all tunnel interfaces.
 pub fn all_tunnel_names() -> Vec<String> {
+    all_tunnel_names_with(&NetdevSource)
+}
+
+/// Get the names of all tunnel interfaces from the given interface source.
+pub fn all_tunnel_names_with(source: &dyn InterfaceSource) -> Vec<String> {
+    all_tunnel_names_with_config(source, &Config::default())
+}
+
+/// Get the names of all tunnel interfaces from the given interface source, which are allowed
+/// by the configuration.
+pub fn all_tunnel_names_with_config(source: &dyn InterfaceSource, config: &Config) -> Vec<String> {
+    tunnel_names(&FilteredSource::from_config(source, config))
+}
+
+/// Get the names of the interfaces flagged as tunnels by the source
+fn tunnel_names(source: &dyn InterfaceSource) -> Vec<String> {
     let mut tunnel_names = vec![];
-    let interfaces = netdev::get_interfaces();
+    let interfaces = source.interfaces();
 
     for interface in interfaces {
         if interface.is_tun() {
@@ -164,8 +904,27 @@ pub fn all_tunnel_names() -> Vec<String> {
 
 /// Get the a list of all tunnel addresses.
 pub fn all_tunnel_addresses() -> HashMap<String, Vec<IpAddr>> {
+    all_tunnel_addresses_with(&NetdevSource)
+}
+
+/// Get the a list of all tunnel addresses from the given interface source.
+pub fn all_tunnel_addresses_with(source: &dyn InterfaceSource) -> HashMap<String, Vec<IpAddr>> {
+    all_tunnel_addresses_with_config(source, &Config::default())
+}
+
+/// Get the a list of all tunnel addresses from the given interface source, of the tunnels
+/// allowed by the configuration.
+pub fn all_tunnel_addresses_with_config(
+    source: &dyn InterfaceSource,
+    config: &Config,
+) -> HashMap<String, Vec<IpAddr>> {
+    tunnel_addresses(&FilteredSource::from_config(source, config))
+}
+
+/// Get the addresses of the interfaces flagged as tunnels by the source
+fn tunnel_addresses(source: &dyn InterfaceSource) -> HashMap<String, Vec<IpAddr>> {
     let mut tunnel_map = HashMap::new();
-    let interfaces = netdev::get_interfaces();
+    let interfaces = source.interfaces();
 
     for interface in interfaces {
         if interface.is_tun() {
@@ -207,16 +966,70 @@ pub fn all_tunnel_addresses() -> HashMap<String, Vec<IpAddr>> {
 /// # }
 /// ```
 pub fn vpn_enabled() -> Result<bool, VpnStatusError> {
-    match status()? {
-        VpnStatus::Enabled | VpnStatus::SplitTunnel => Ok(true),
+    vpn_enabled_with(&NetdevSource)
+}
+
+/// Get the status of the VPN connection from the given interface source.
+pub fn vpn_enabled_with(source: &dyn InterfaceSource) -> Result<bool, VpnStatusError> {
+    vpn_enabled_with_config(source, &Config::default())
+}
+
+/// Get the status of the VPN connection from the given interface source and configuration.
+///
+/// The VPN is enabled while a tunnel carries traffic, also when the chain of tunnels is shorter
+/// than `min_hops` or destinations are routed against `must_tunnel` or `must_bypass`. A stalled
+/// tunnel does not carry traffic, so the VPN is not enabled.
+pub fn vpn_enabled_with_config(
+    source: &dyn InterfaceSource,
+    config: &Config,
+) -> Result<bool, VpnStatusError> {
+    match detect_with(source, config)?.status {
+        VpnStatus::Enabled
+        | VpnStatus::SplitTunnel
+        | VpnStatus::ShortChain
+        | VpnStatus::Violation => Ok(true),
         _ => Ok(false),
     }
 }
 
 pub fn status_string(config: Config, no_style: bool) -> Result<String, VpnStatusError> {
+    status_string_with(&NetdevSource, config, no_style)
+}
+
+/// Get the formatted status string of a network namespace, e.g. of a container.
+///
+/// The public IP address is looked up from inside the namespace. When the namespace can not be
+/// entered, the lookup tokens are left empty rather than showing the address of the host.
+pub fn status_string_in(
+    namespace: &Namespace,
+    config: Config,
+    no_style: bool,
+) -> Result<String, VpnStatusError> {
+    let source = namespace.capture()?;
+    let inside = (source.clone(), config.clone());
+    match namespace.run(move || status_string_with(&inside.0, inside.1, no_style)) {
+        Ok(output) => output,
+        Err(error) => {
+            debug!("{}, skipping the lookup", error);
+            let config = Config {
+                lookup: Some(false),
+                ..config
+            };
+            status_string_with(&source, config, no_style)
+        }
+    }
+}
+
+/// Get the formatted status string from the given interface source.
+pub fn status_string_with(
+    source: &dyn InterfaceSource,
+    config: Config,
+    no_style: bool,
+) -> Result<String, VpnStatusError> {
     debug!("{:#?}", config);
 
-    let status = self::status()?;
+    let detection = self::detect_with(source, &config)?;
+    let status = detection.status;
 
     // get the custom status string if it exists
     let mut status_string: String = {
@@ -224,6 +1037,14 @@ pub fn status_string(config: Config, no_style: bool) -> Result<String, VpnStatus
             VpnStatus::Enabled => config.clone().enabled_string,
             VpnStatus::Disabled => config.clone().disabled_string,
             VpnStatus::SplitTunnel => config.clone().split_tunnel_string,
+            VpnStatus::Mesh => config.clone().mesh_string,
+            VpnStatus::Stalled => config.clone().stalled_string,
+            VpnStatus::Connecting => config.clone().connecting_string,
+            VpnStatus::ShortChain => config.clone().short_chain_string,
+            VpnStatus::Violation => config.clone().violation_string,
+            VpnStatus::Trusted => config.clone().trusted_string,
+            VpnStatus::CaptivePortal => config.clone().captive_portal_string,
+            VpnStatus::NoGateway => config.clone().no_gateway_string,
             VpnStatus::Offline => config.clone().offline_string,
         };
         custom_status.unwrap_or(format!("{}", status))
@@ -253,7 +1074,69 @@ pub fn status_string(config: Config, no_style: bool) -> Result<String, VpnStatus
                     "".to_string()
                 }
             }
-            VpnStatus::Offline => "".to_string(),
+            VpnStatus::Mesh => {
+                if let Some(ref style) = config.mesh_style {
+                    style.color.clone()
+                } else {
+                    "".to_string()
+                }
+            }
+            VpnStatus::Stalled => {
+                if let Some(ref style) = config.stalled_style {
+                    style.color.clone()
+                } else {
+                    "".to_string()
+                }
+            }
+            VpnStatus::Connecting => {
+                if let Some(ref style) = config.connecting_style {
+                    style.color.clone()
+                } else {
+                    "".to_string()
+                }
+            }
+            VpnStatus::ShortChain => {
+                if let Some(ref style) = config.short_chain_style {
+                    style.color.clone()
+                } else {
+                    "".to_string()
+                }
+            }
+            VpnStatus::Violation => {
+                if let Some(ref style) = config.violation_style {
+                    style.color.clone()
+                } else {
+                    "".to_string()
+                }
+            }
+            VpnStatus::Trusted => {
+                if let Some(ref style) = config.trusted_style {
+                    style.color.clone()
+                } else {
+                    "".to_string()
+                }
+            }
+            VpnStatus::CaptivePortal => {
+                if let Some(ref style) = config.captive_portal_style {
+                    style.color.clone()
+                } else {
+                    "".to_string()
+                }
+            }
+            VpnStatus::NoGateway => {
+                if let Some(ref style) = config.no_gateway_style {
+                    style.color.clone()
+                } else {
+                    "".to_string()
+                }
+            }
+            VpnStatus::Offline => {
+                if let Some(ref style) = config.offline_style {
+                    style.color.clone()
+                } else {
+                    "".to_string()
+                }
+            }
         };
 
         // get the custom style if it exists
@@ -279,18 +1162,81 @@ pub fn status_string(config: Config, no_style: bool) -> Result<String, VpnStatus
                     vec![]
                 }
             }
-            VpnStatus::Offline => {
-                vec![]
+            VpnStatus::Mesh => {
+                if let Some(style) = config.mesh_style.clone() {
+                    style.format.unwrap_or_default()
+                } else {
+                    vec![]
+                }
             }
-        };
-
+            VpnStatus::Stalled => {
+                if let Some(style) = config.stalled_style.clone() {
+                    style.format.unwrap_or_default()
+                } else {
+                    vec![]
+                }
+            }
+            VpnStatus::Connecting => {
+                if let Some(style) = config.connecting_style.clone() {
+                    style.format.unwrap_or_default()
+                } else {
+                    vec![]
+                }
+            }
+            VpnStatus::ShortChain => {
+                if let Some(style) = config.short_chain_style.clone() {
+                    style.format.unwrap_or_default()
+                } else {
+                    vec![]
+                }
+            }
+            VpnStatus::Violation => {
+                if let Some(style) = config.violation_style.clone() {
+                    style.format.unwrap_or_default()
+                } else {
+                    vec![]
+                }
+            }
+            VpnStatus::Trusted => {
+                if let Some(style) = config.trusted_style.clone() {
+                    style.format.unwrap_or_default()
+                } else {
+                    vec![]
+                }
+            }
+            VpnStatus::CaptivePortal => {
+                if let Some(style) = config.captive_portal_style.clone() {
+                    style.format.unwrap_or_default()
+                } else {
+                    vec![]
+                }
+            }
+            VpnStatus::NoGateway => {
+                if let Some(style) = config.no_gateway_style.clone() {
+                    style.format.unwrap_or_default()
+                } else {
+                    vec![]
+                }
+            }
+            VpnStatus::Offline => {
+                if let Some(style) = config.offline_style.clone() {
+                    style.format.unwrap_or_default()
+                } else {
+                    vec![]
+                }
+            }
+        };
+
         // apply the styles to the output
         status_string = styles::apply_style(status_string, custom_style, &custom_color);
     }
 
     // lookup the public ip address if the flag is set
-    let lookup = if status == VpnStatus::Offline {
-        // we are offline, no need to lookup
+    let lookup = if matches!(
+        status,
+        VpnStatus::Offline | VpnStatus::NoGateway | VpnStatus::CaptivePortal
+    ) {
+        // the internet is not reachable, no need to lookup
         None
     } else if config.lookup.unwrap_or(false) {
         // get custom lookup color
@@ -307,21 +1253,7 @@ pub fn status_string(config: Config, no_style: bool) -> Result<String, VpnStatus
             vec![]
         };
 
-        // get custom providers list
-        let providers = if let Some(providers) = config.lookup_providers {
-            providers
-                .into_iter()
-                .map(|p| (p.parse::<LookupProvider>().unwrap(), None))
-                .collect()
-        } else {
-            vec![]
-        };
-
-        let response = if providers.is_empty() {
-            public_ip_address::perform_lookup(None)?
-        } else {
-            public_ip_address::perform_cached_lookup_with(providers, None, Some(2), false)?
-        };
+        let response = lookup_public_ip(&config.lookup_providers)?;
 
         Some(parser::Lookup {
             ip: styles::apply_style(response.ip.to_string(), lookup_style.clone(), &lookup_color),
@@ -340,6 +1272,174 @@ pub fn status_string(config: Config, no_style: bool) -> Result<String, VpnStatus
         None
     };
 
+    // describe the detection details
+    let details = {
+        // leaking address family, if any
+        let leak = match detection.leak {
+            Some(family) => apply_style_config(
+                config
+                    .leak_string
+                    .clone()
+                    .unwrap_or(format!("{} leak", family)),
+                config.leak_style.as_ref(),
+                no_style,
+            ),
+            None => "".to_string(),
+        };
+
+        let dns = detection
+            .nameservers
+            .iter()
+            .map(|nameserver| nameserver.address.to_string())
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        let dns_leak = if detection.dns_leak.is_empty() {
+            "".to_string()
+        } else {
+            apply_style_config(
+                config
+                    .dns_leak_string
+                    .clone()
+                    .unwrap_or("dns leak".to_string()),
+                config.dns_leak_style.as_ref(),
+                no_style,
+            )
+        };
+
+        // kind of the tunnel carrying the default route, or of all tunnels
+        let provider = match detection.active_tunnel() {
+            Some(tunnel) => tunnel.kind.to_string(),
+            None => {
+                let mut kinds: Vec<String> = vec![];
+                for tunnel in &detection.tunnels {
+                    let kind = tunnel.kind.to_string();
+                    if !kinds.contains(&kind) {
+                        kinds.push(kind);
+                    }
+                }
+                if !detection.ipsec.is_empty() {
+                    kinds.push(kind::TunnelKind::Ipsec.to_string());
+                }
+                kinds.join(", ")
+            }
+        };
+
+        // traffic rates summed over all tunnels, unknown on the first run
+        let rate = |rate: fn(&traffic::Traffic) -> Option<f64>| {
+            let rates: Vec<f64> = detection.traffic.values().filter_map(rate).collect();
+            if rates.is_empty() {
+                "".to_string()
+            } else {
+                traffic::format_rate(rates.iter().sum())
+            }
+        };
+        let rx = rate(|traffic| traffic.rx_rate);
+        let tx = rate(|traffic| traffic.tx_rate);
+
+        // latest handshake of the active WireGuard tunnel, or of any WireGuard tunnel
+        let peers: Vec<wireguard::Peer> = match detection
+            .active_tunnel()
+            .and_then(|tunnel| detection.wireguard.get(&tunnel.name))
+        {
+            Some(peers) => peers.clone(),
+            None => detection.wireguard.values().flatten().cloned().collect(),
+        };
+        let peer = wireguard::latest_peer(&peers);
+        let handshake = peer
+            .and_then(|peer| peer.handshake_age(detection.timestamp))
+            .map(traffic::format_duration)
+            .unwrap_or_default();
+        let endpoint = peer
+            .or(peers.first())
+            .and_then(|peer| peer.endpoint)
+            .map(|endpoint| endpoint.to_string())
+            .unwrap_or_default();
+
+        let uptime = match detection.uptime() {
+            Some(uptime) => {
+                let format = match config.uptime_format {
+                    Some(ref format) => format.parse().unwrap_or_else(|error| {
+                        debug!("{}", error);
+                        traffic::DurationFormat::default()
+                    }),
+                    None => traffic::DurationFormat::default(),
+                };
+                format.format(uptime)
+            }
+            None => "".to_string(),
+        };
+
+        // configured destinations routed against the tunnel policy
+        let mut violations: Vec<&str> = vec![];
+        for violation in &detection.violations {
+            if !violations.contains(&violation.destination.as_str()) {
+                violations.push(&violation.destination);
+            }
+        }
+        let violations = violations.join(", ");
+
+        // client running the active tunnel, or all running clients
+        let client = match detection.active_tunnel().and_then(|tunnel| {
+            detection
+                .clients
+                .iter()
+                .find(|client| client.interface.as_ref() == Some(&tunnel.name))
+        }) {
+            Some(client) => client.name.clone(),
+            None => {
+                let mut names: Vec<&str> = vec![];
+                for client in &detection.clients {
+                    if !names.contains(&client.name.as_str()) {
+                        names.push(&client.name);
+                    }
+                }
+                names.join(", ")
+            }
+        };
+
+        // NetworkManager connection of the active tunnel, or all connections
+        let connection = match detection.active_tunnel().and_then(|tunnel| {
+            detection
+                .connections
+                .iter()
+                .find(|connection| connection.interface.as_ref() == Some(&tunnel.name))
+        }) {
+            Some(connection) => connection.id.clone(),
+            None => detection
+                .connections
+                .iter()
+                .filter(|connection| {
+                    matches!(
+                        connection.state,
+                        networkmanager::ConnectionState::Activating
+                            | networkmanager::ConnectionState::Activated
+                    )
+                })
+                .map(|connection| connection.id.as_str())
+                .collect::<Vec<&str>>()
+                .join(", "),
+        };
+
+        let chain = chain::format(&detection.chain);
+
+        parser::Details {
+            leak,
+            dns,
+            dns_leak,
+            provider,
+            rx,
+            tx,
+            handshake,
+            endpoint,
+            uptime,
+            violations,
+            client,
+            connection,
+            chain,
+        }
+    };
+
     // get custom output format if it exists
     let format = match config.output_format {
         Some(format) => format,
@@ -353,7 +1453,7 @@ pub fn status_string(config: Config, no_style: bool) -> Result<String, VpnStatus
     };
 
     let output = if no_style {
-        parser::make_output(parser::parse(&format), &status_string, lookup)
+        parser::make_output_with_details(parser::parse(&format), &status_string, lookup, details)
     } else {
         // get custom color
         let color = if let Some(ref style) = config.output_style {
@@ -368,10 +1468,11 @@ pub fn status_string(config: Config, no_style: bool) -> Result<String, VpnStatus
         } else {
             vec![]
         };
-        parser::make_output_styled(
+        parser::make_output_styled_with_details(
             parser::parse(&format),
             &status_string,
             lookup,
+            details,
             style,
             &color,
         )
@@ -379,9 +1480,70 @@ pub fn status_string(config: Config, no_style: bool) -> Result<String, VpnStatus
     Ok(output)
 }
 
+/// Look up the public IP address with the listed providers, or the default ones
+fn lookup_public_ip(
+    providers: &Option<Vec<String>>,
+) -> Result<public_ip_address::response::LookupResponse, VpnStatusError> {
+    let providers: Vec<_> = providers
+        .iter()
+        .flatten()
+        .map(|p| (p.parse::<LookupProvider>().unwrap(), None))
+        .collect();
+    let response = if providers.is_empty() {
+        public_ip_address::perform_lookup(None)?
+    } else {
+        public_ip_address::perform_cached_lookup_with(providers, None, Some(2), false)?
+    };
+    Ok(response)
+}
+
+/// Get the addresses of all interfaces
+fn local_addresses(source: &dyn InterfaceSource) -> Vec<IpAddr> {
+    source
+        .interfaces()
+        .iter()
+        .flat_map(|interface| {
+            let ipv4 = interface.ipv4.iter().map(|net| IpAddr::V4(net.addr()));
+            let ipv6 = interface.ipv6.iter().map(|net| IpAddr::V6(net.addr()));
+            ipv4.chain(ipv6).collect::<Vec<IpAddr>>()
+        })
+        .collect()
+}
+
+/// Get the current time in seconds since the unix epoch
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+/// Check whether an address is only valid on its link
+fn is_link_local(address: &IpAddr) -> bool {
+    match address {
+        IpAddr::V4(address) => address.is_link_local(),
+        IpAddr::V6(address) => address.segments()[0] & 0xffc0 == 0xfe80,
+    }
+}
+
+/// Apply an optional style configuration to a string
+fn apply_style_config(input: String, style: Option<&StyleConfig>, no_style: bool) -> String {
+    match style {
+        Some(style) if !no_style => styles::apply_style(
+            input,
+            style.format.clone().unwrap_or_default(),
+            &style.color,
+        ),
+        _ => input,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use kind::{LinkInfo, TunnelKind};
+    use rules::{Rule, RuleAction};
+    use source::MemorySource;
 
     #[test]
     fn test_status() {
@@ -394,4 +1556,965 @@ mod tests {
         let result = vpn_enabled();
         assert!(result.is_ok(), "Failed to check vpn_enabled: {:?}", result);
     }
+
+    const IFF_UP: u32 = 0x1;
+    const IFF_BROADCAST: u32 = 0x2;
+    const IFF_POINTOPOINT: u32 = 0x10;
+
+    fn interface(name: &str, flags: u32, ipv4: &str, default: bool) -> netdev::Interface {
+        let mut interface = netdev::Interface::dummy();
+        interface.name = name.to_string();
+        interface.flags = flags;
+        interface.ipv4 = vec![ipv4.parse().unwrap()];
+        interface.default = default;
+        interface
+    }
+
+    fn ethernet(default: bool) -> netdev::Interface {
+        interface("eth0", IFF_UP | IFF_BROADCAST, "192.168.1.10/24", default)
+    }
+
+    fn tunnel(default: bool) -> netdev::Interface {
+        interface("tun0", IFF_UP | IFF_POINTOPOINT, "10.8.0.2/24", default)
+    }
+
+    fn route(destination: &str, interface: &str) -> routes::Route {
+        routes::Route::new(destination.parse().unwrap(), interface)
+    }
+
+    #[test]
+    fn test_status_enabled() {
+        let source = MemorySource::new(vec![ethernet(false), tunnel(true)]);
+        assert_eq!(status_with(&source).unwrap(), VpnStatus::Enabled);
+        assert!(vpn_enabled_with(&source).unwrap());
+        assert_eq!(tunnel_name_with(&source).unwrap(), "tun0");
+        assert_eq!(
+            tunnel_address_with(&source).unwrap(),
+            vec!["10.8.0.2".parse::<IpAddr>().unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_status_split_tunnel() {
+        let source = MemorySource::new(vec![ethernet(true), tunnel(false)]);
+        assert_eq!(status_with(&source).unwrap(), VpnStatus::SplitTunnel);
+        assert!(vpn_enabled_with(&source).unwrap());
+        assert!(tunnel_name_with(&source).is_err());
+        assert_eq!(all_tunnel_names_with(&source), vec!["tun0".to_string()]);
+        assert_eq!(
+            all_tunnel_addresses_with(&source).get("tun0"),
+            Some(&vec!["10.8.0.2".parse::<IpAddr>().unwrap()])
+        );
+    }
+
+    #[test]
+    fn test_status_def1_routes() {
+        let source = MemorySource::new(vec![ethernet(true), tunnel(false)]).with_routes(vec![
+            route("0.0.0.0/0", "eth0"),
+            route("192.168.1.0/24", "eth0"),
+            route("0.0.0.0/1", "tun0"),
+            route("128.0.0.0/1", "tun0"),
+        ]);
+        assert_eq!(status_with(&source).unwrap(), VpnStatus::Enabled);
+    }
+
+    #[test]
+    fn test_status_split_routes() {
+        let source = MemorySource::new(vec![ethernet(true), tunnel(false)]).with_routes(vec![
+            route("0.0.0.0/0", "eth0"),
+            route("10.0.0.0/8", "tun0"),
+        ]);
+        assert_eq!(status_with(&source).unwrap(), VpnStatus::SplitTunnel);
+    }
+
+    #[test]
+    fn test_status_policy_routing() {
+        let mut wg0 = interface("wg0", IFF_UP | IFF_POINTOPOINT, "10.2.0.2/32", false);
+        wg0.ipv6 = vec![];
+        let mut wg0_default = route("0.0.0.0/0", "wg0");
+        wg0_default.table = 51820;
+        let mut suppress = Rule::new(AddressFamily::Ipv4, 32764, RuleAction::Lookup(254));
+        suppress.suppress_prefix_length = Some(0);
+        let mut fwmark = Rule::new(AddressFamily::Ipv4, 32765, RuleAction::Lookup(51820));
+        fwmark.fwmark = Some(0xca6c);
+        fwmark.invert = true;
+
+        let source = MemorySource::new(vec![ethernet(true), wg0])
+            .with_routes(vec![route("0.0.0.0/0", "eth0"), wg0_default])
+            .with_rules(vec![
+                suppress,
+                fwmark,
+                Rule::new(AddressFamily::Ipv4, 32766, RuleAction::Lookup(254)),
+            ]);
+        let detection = detect_with(&source, &Config::default()).unwrap();
+        assert_eq!(detection.status, VpnStatus::Enabled);
+        let route = detection.route.unwrap();
+        assert_eq!(route.route.interface, "wg0");
+        assert_eq!(route.route.table, 51820);
+        assert_eq!(route.rule.unwrap().priority, 32765);
+
+        // without the fwmark rule the main table default route is used
+        let source = source.with_rules(vec![]);
+        let detection = detect_with(&source, &Config::default()).unwrap();
+        assert_eq!(detection.status, VpnStatus::SplitTunnel);
+        assert_eq!(detection.route.unwrap().route.interface, "eth0");
+    }
+
+    #[test]
+    fn test_ipv6_leak() {
+        let source = MemorySource::new(vec![ethernet(false), tunnel(true)])
+            .with_routes(vec![route("0.0.0.0/0", "tun0"), route("::/0", "eth0")]);
+        let detection = detect_with(&source, &Config::default()).unwrap();
+        assert_eq!(detection.status, VpnStatus::Enabled);
+        assert_eq!(detection.leak, Some(AddressFamily::Ipv6));
+
+        let source = source.with_routes(vec![route("0.0.0.0/0", "tun0"), route("::/0", "tun0")]);
+        assert_eq!(detect_with(&source, &Config::default()).unwrap().leak, None);
+
+        // no IPv6 connectivity at all is not a leak
+        let source = source.with_routes(vec![route("0.0.0.0/0", "tun0")]);
+        assert_eq!(detect_with(&source, &Config::default()).unwrap().leak, None);
+    }
+
+    #[test]
+    fn test_ipv4_leak() {
+        let source = MemorySource::new(vec![ethernet(true), tunnel(false)])
+            .with_routes(vec![route("0.0.0.0/0", "eth0"), route("::/0", "tun0")]);
+        let detection = detect_with(&source, &Config::default()).unwrap();
+        assert_eq!(detection.status, VpnStatus::SplitTunnel);
+        assert_eq!(detection.leak, Some(AddressFamily::Ipv4));
+    }
+
+    #[test]
+    fn test_dns_leak() {
+        let source = MemorySource::new(vec![ethernet(false), tunnel(true)])
+            .with_routes(vec![
+                route("0.0.0.0/0", "tun0"),
+                route("192.168.1.0/24", "eth0"),
+            ])
+            .with_nameservers(vec![
+                Nameserver::new("10.8.0.1".parse().unwrap()),
+                Nameserver::new("192.168.1.1".parse().unwrap()),
+            ]);
+        let detection = detect_with(&source, &Config::default()).unwrap();
+        assert_eq!(detection.nameservers.len(), 2);
+        assert_eq!(
+            detection.dns_leak,
+            vec![Nameserver::new("192.168.1.1".parse().unwrap())]
+        );
+
+        // without a tunnel there is nothing to leak from
+        let source = MemorySource::new(vec![ethernet(true)])
+            .with_routes(vec![route("0.0.0.0/0", "eth0")])
+            .with_nameservers(vec![Nameserver::new("192.168.1.1".parse().unwrap())]);
+        assert!(detect_with(&source, &Config::default())
+            .unwrap()
+            .dns_leak
+            .is_empty());
+    }
+
+    #[test]
+    fn test_provider() {
+        let mut wg = interface("work", IFF_UP | IFF_POINTOPOINT, "10.2.0.2/32", false);
+        wg.ipv6 = vec![];
+        let source = MemorySource::new(vec![ethernet(false), tunnel(true), wg])
+            .with_links(HashMap::from([(
+                "work".to_string(),
+                LinkInfo {
+                    device_type: Some("wireguard".to_string()),
+                    ..Default::default()
+                },
+            )]))
+            .with_routes(vec![route("0.0.0.0/0", "tun0")]);
+        let detection = detect_with(&source, &Config::default()).unwrap();
+        assert_eq!(
+            detection.tunnels,
+            vec![
+                Tunnel {
+                    name: "tun0".to_string(),
+                    kind: TunnelKind::OpenVpn,
+                    mesh: false,
+                },
+                Tunnel {
+                    name: "work".to_string(),
+                    kind: TunnelKind::WireGuard,
+                    mesh: false,
+                },
+            ]
+        );
+        assert_eq!(detection.active_tunnel().unwrap().name, "tun0");
+
+        let config = Config {
+            output_format: Some("{status} ({provider})".to_string()),
+            providers: Some(HashMap::from([(
+                "tun*".to_string(),
+                "Corp VPN".to_string(),
+            )])),
+            ..Default::default()
+        };
+        let output = status_string_with(&source, config, true).unwrap();
+        assert_eq!(output, "enabled (Corp VPN)");
+
+        // without a tunnel carrying the default route all kinds are listed
+        let source = source.with_routes(vec![route("0.0.0.0/0", "eth0")]);
+        let config = Config {
+            output_format: Some("{provider}".to_string()),
+            ..Default::default()
+        };
+        let output = status_string_with(&source, config, true).unwrap();
+        assert_eq!(output, "openvpn, wireguard");
+    }
+
+    #[test]
+    fn test_status_mesh() {
+        let tailscale = interface(
+            "tailscale0",
+            IFF_UP | IFF_POINTOPOINT,
+            "100.101.102.103/32",
+            false,
+        );
+        let mut tailscale_route = route("100.64.0.0/10", "tailscale0");
+        tailscale_route.table = 52;
+        let source = MemorySource::new(vec![ethernet(true), tailscale])
+            .with_routes(vec![route("0.0.0.0/0", "eth0"), tailscale_route.clone()]);
+        assert_eq!(status_with(&source).unwrap(), VpnStatus::Mesh);
+        assert!(!vpn_enabled_with(&source).unwrap());
+        let detection = detect_with(&source, &Config::default()).unwrap();
+        assert!(detection.tunnels[0].mesh);
+
+        // a real split tunnel next to the mesh takes precedence
+        let mut interfaces = source.interfaces.clone();
+        interfaces.push(tunnel(false));
+        let mut routes = source.routes.clone();
+        routes.push(route("10.8.0.0/24", "tun0"));
+        routes.push(route("8.8.8.0/24", "tun0"));
+        let split = MemorySource::new(interfaces).with_routes(routes);
+        assert_eq!(status_with(&split).unwrap(), VpnStatus::SplitTunnel);
+
+        // an exit node routes public destinations through the overlay
+        let mut exit_route = route("0.0.0.0/0", "tailscale0");
+        exit_route.table = 52;
+        let mut bypass = Rule::new(AddressFamily::Ipv4, 5210, RuleAction::Lookup(254));
+        bypass.fwmark = Some(0x80000);
+        bypass.fwmask = Some(0xff0000);
+        let mut rules = vec![
+            bypass,
+            Rule::new(AddressFamily::Ipv4, 5270, RuleAction::Lookup(52)),
+        ];
+        rules.extend(rules::default_rules(AddressFamily::Ipv4));
+        let exit_node = source
+            .clone()
+            .with_routes(vec![
+                route("0.0.0.0/0", "eth0"),
+                tailscale_route,
+                exit_route,
+            ])
+            .with_rules(rules);
+        assert_eq!(status_with(&exit_node).unwrap(), VpnStatus::Enabled);
+
+        // unknown interfaces are overlays when they only use overlay ranges
+        let overlay = interface("mesh0", IFF_UP | IFF_POINTOPOINT, "100.80.0.1/16", false);
+        let source = MemorySource::new(vec![ethernet(true), overlay]);
+        assert_eq!(status_with(&source).unwrap(), VpnStatus::Mesh);
+
+        let config = Config {
+            output_format: Some("{status} {provider}".to_string()),
+            ..Default::default()
+        };
+        let output = status_string_with(&source, config, true).unwrap();
+        assert_eq!(output, "mesh unknown");
+    }
+
+    #[test]
+    fn test_status_ipsec() {
+        let mut policy = ipsec::Policy::new(
+            ipsec::PolicyDirection::Out,
+            "192.168.1.10/32".parse().unwrap(),
+            "0.0.0.0/0".parse().unwrap(),
+        );
+        policy.templates.push(ipsec::Template {
+            destination: "203.0.113.1".parse().unwrap(),
+            reqid: 1,
+            tunnel: true,
+        });
+        let state = ipsec::State {
+            source: "192.168.1.10".parse().unwrap(),
+            destination: "203.0.113.1".parse().unwrap(),
+            spi: 0xc1a2b3c4,
+            reqid: 1,
+            tunnel: true,
+        };
+        let source = MemorySource::new(vec![ethernet(true)])
+            .with_routes(vec![route("0.0.0.0/0", "eth0")])
+            .with_nameservers(vec![Nameserver::new("192.168.1.1".parse().unwrap())])
+            .with_ipsec(vec![policy.clone()], vec![state.clone()]);
+        let detection = detect_with(&source, &Config::default()).unwrap();
+        assert_eq!(detection.status, VpnStatus::Enabled);
+        assert_eq!(detection.ipsec, vec![policy.clone()]);
+        assert!(detection.dns_leak.is_empty());
+
+        let config = Config {
+            output_format: Some("{status} {provider}".to_string()),
+            ..Default::default()
+        };
+        let output = status_string_with(&source, config, true).unwrap();
+        assert_eq!(output, "enabled ipsec");
+
+        // selectors for the corporate network only
+        policy.destination = "10.0.0.0/8".parse().unwrap();
+        let split = source.clone().with_ipsec(vec![policy.clone()], vec![state]);
+        assert_eq!(status_with(&split).unwrap(), VpnStatus::SplitTunnel);
+
+        // the policy is installed but the connection is not established
+        let idle = source.clone().with_ipsec(vec![policy], vec![]);
+        assert_eq!(status_with(&idle).unwrap(), VpnStatus::Disabled);
+
+        // checking a single tunnel ignores the established policy
+        let work = interface("wg-work", IFF_POINTOPOINT, "10.2.0.2/32", false);
+        let mut single = source;
+        single.interfaces.push(work);
+        assert_eq!(status_with(&single).unwrap(), VpnStatus::Enabled);
+        assert_eq!(
+            status_for_interface_with(&single, "wg-work").unwrap(),
+            VpnStatus::Disabled
+        );
+    }
+
+    /// Source failing to read the WireGuard peers, like an unprivileged user
+    struct Unprivileged(MemorySource);
+
+    impl InterfaceSource for Unprivileged {
+        fn default_interface(&self) -> Result<netdev::Interface, String> {
+            self.0.default_interface()
+        }
+
+        fn interfaces(&self) -> Vec<netdev::Interface> {
+            self.0.interfaces()
+        }
+
+        fn try_wireguard_peers(&self, _name: &str) -> Result<Vec<wireguard::Peer>, VpnStatusError> {
+            let error = std::io::Error::from(std::io::ErrorKind::PermissionDenied);
+            Err(VpnStatusError::backend("wireguard peers", error))
+        }
+    }
+
+    #[test]
+    fn test_backend_warnings() {
+        let wg = interface("wg0", IFF_UP | IFF_POINTOPOINT, "10.2.0.2/32", true);
+        let source = Unprivileged(MemorySource::new(vec![ethernet(false), wg]));
+        let detection = detect_with(&source, &Config::default()).unwrap();
+        assert_eq!(detection.status, VpnStatus::Enabled);
+        assert_eq!(detection.warnings.len(), 1);
+        assert!(matches!(
+            detection.warnings[0].error(),
+            VpnStatusError::PermissionDenied { backend, .. } if backend == "wireguard peers"
+        ));
+
+        let source = MemorySource::new(vec![ethernet(false), tunnel(true)]);
+        assert!(detect_with(&source, &Config::default())
+            .unwrap()
+            .warnings
+            .is_empty());
+    }
+
+    #[test]
+    fn test_status_stalled() {
+        let state_path = std::env::temp_dir().join(format!(
+            "vpn_status_state_stalled_{}.json",
+            std::process::id()
+        ));
+        let config = Config {
+            output_format: Some("{status} {rx} {tx}".to_string()),
+            stall_timeout: Some(0),
+            state_path: Some(state_path.clone()),
+            ..Default::default()
+        };
+        let source = |rx_bytes, tx_bytes| {
+            MemorySource::new(vec![ethernet(false), tunnel(true)]).with_counters(HashMap::from([(
+                "tun0".to_string(),
+                traffic::Counters { rx_bytes, tx_bytes },
+            )]))
+        };
+
+        let output = status_string_with(&source(1000, 1000), config.clone(), true).unwrap();
+        assert_eq!(output, "enabled  ");
+        let detection = detect_with(&source(2000, 1500), &config).unwrap();
+        assert_eq!(detection.status, VpnStatus::Enabled);
+        assert!(detection.traffic["tun0"].rx_rate.is_some());
+
+        // transmitting without receiving a reply
+        let detection = detect_with(&source(2000, 3000), &config).unwrap();
+        assert_eq!(detection.status, VpnStatus::Stalled);
+        std::fs::remove_file(state_path).unwrap();
+    }
+
+    #[test]
+    fn test_wireguard_handshake() {
+        let wg = interface("wg0", IFF_UP | IFF_POINTOPOINT, "10.2.0.2/32", true);
+        let peer = wireguard::Peer {
+            public_key: "bWFkZSB1cCBwdWJsaWMga2V5IGZvciB0ZXN0aW5nIQ==".to_string(),
+            endpoint: Some("198.51.100.7:51820".parse().unwrap()),
+            last_handshake: Some(now() - 42),
+            rx_bytes: 4096,
+            tx_bytes: 1024,
+        };
+        let source = MemorySource::new(vec![ethernet(false), wg])
+            .with_routes(vec![route("0.0.0.0/0", "wg0")])
+            .with_wireguard_peers(HashMap::from([("wg0".to_string(), vec![peer.clone()])]));
+        let config = Config {
+            output_format: Some("{status} {endpoint} {handshake}".to_string()),
+            ..Default::default()
+        };
+        let output = status_string_with(&source, config.clone(), true).unwrap();
+        assert!(output.starts_with("enabled 198.51.100.7:51820 4"));
+
+        // the peer does not answer anymore
+        let mut stale = peer;
+        stale.last_handshake = Some(now() - 600);
+        let source = source.with_wireguard_peers(HashMap::from([("wg0".to_string(), vec![stale])]));
+        assert_eq!(status_with(&source).unwrap(), VpnStatus::Stalled);
+        assert!(!vpn_enabled_with(&source).unwrap());
+        let output = status_string_with(&source, config, true).unwrap();
+        assert_eq!(output, "stalled 198.51.100.7:51820 10m 0s");
+    }
+
+    #[test]
+    fn test_uptime() {
+        let state_path = std::env::temp_dir().join(format!(
+            "vpn_status_state_uptime_{}.json",
+            std::process::id()
+        ));
+        let mut state = state::State::default();
+        state.update_connection(true, now() - 3 * 3600 - 65);
+        state.save(&state_path).unwrap();
+        let config = Config {
+            output_format: Some("{status} for {uptime}".to_string()),
+            uptime_format: Some("clock".to_string()),
+            state_path: Some(state_path.clone()),
+            ..Default::default()
+        };
+
+        let source = MemorySource::new(vec![ethernet(false), tunnel(true)]);
+        let output = status_string_with(&source, config.clone(), true).unwrap();
+        assert!(output.starts_with("enabled for 03:01:0"));
+
+        // disconnecting resets the uptime
+        let source = MemorySource::new(vec![ethernet(true)]);
+        let detection = detect_with(&source, &config).unwrap();
+        assert_eq!(detection.uptime(), None);
+        let output = status_string_with(&source, config.clone(), true).unwrap();
+        assert_eq!(output, "disabled for ");
+
+        let source = MemorySource::new(vec![ethernet(false), tunnel(true)]);
+        let detection = detect_with(&source, &config).unwrap();
+        assert!(detection.uptime().unwrap() < Duration::from_secs(5));
+        std::fs::remove_file(state_path).unwrap();
+    }
+
+    #[test]
+    fn test_interface_state() {
+        let state_path = std::env::temp_dir().join(format!(
+            "vpn_status_state_interface_{}.json",
+            std::process::id()
+        ));
+        let interface_path = state::interface_state_path(&state_path, "tun0");
+        let config = Config {
+            interface: Some("tun0".to_string()),
+            state_path: Some(state_path.clone()),
+            ..Default::default()
+        };
+
+        let source = MemorySource::new(vec![ethernet(false), tunnel(true)]);
+        detect_with(&source, &config).unwrap();
+        assert!(!state_path.exists());
+        assert!(state::State::load(&interface_path)
+            .connected_since
+            .is_some());
+        std::fs::remove_file(interface_path).unwrap();
+    }
+
+    #[test]
+    fn test_interface_filters() {
+        let cni = interface("cni0", IFF_UP | IFF_POINTOPOINT, "10.88.0.1/16", false);
+        let source = MemorySource::new(vec![ethernet(true), cni]);
+        assert_eq!(status_with(&source).unwrap(), VpnStatus::SplitTunnel);
+
+        let config = Config {
+            exclude_interfaces: Some(vec!["cni*".to_string()]),
+            ..Default::default()
+        };
+        let detection = detect_with(&source, &config).unwrap();
+        assert_eq!(detection.status, VpnStatus::Disabled);
+        assert!(detection.tunnels.is_empty());
+
+        assert_eq!(all_tunnel_names_with(&source), vec!["cni0".to_string()]);
+        assert!(all_tunnel_names_with_config(&source, &config).is_empty());
+        assert!(all_tunnel_addresses_with_config(&source, &config).is_empty());
+
+        // only the listed interfaces are tunnels
+        let source = MemorySource::new(vec![ethernet(false), tunnel(true)]);
+        let config = Config {
+            include_interfaces: Some(vec!["wg*".to_string()]),
+            ..Default::default()
+        };
+        assert!(tunnel_name_with_config(&source, &config).is_err());
+        assert!(tunnel_address_with_config(&source, &config).is_err());
+        assert_eq!(tunnel_name_with(&source).unwrap(), "tun0");
+        assert_eq!(
+            detect_with(&source, &config).unwrap().status,
+            VpnStatus::Disabled
+        );
+    }
+
+    #[test]
+    fn test_status_for_interface() {
+        let mut wg = interface("wg-work", IFF_UP | IFF_POINTOPOINT, "10.2.0.2/32", false);
+        wg.ipv6 = vec![];
+        let personal = interface("wg-home", IFF_UP | IFF_POINTOPOINT, "10.3.0.2/32", false);
+        let source = MemorySource::new(vec![ethernet(true), wg.clone(), personal.clone()])
+            .with_routes(vec![
+                route("0.0.0.0/0", "eth0"),
+                route("0.0.0.0/1", "wg-home"),
+                route("128.0.0.0/1", "wg-home"),
+                route("10.0.0.0/8", "wg-work"),
+            ]);
+        assert_eq!(status_with(&source).unwrap(), VpnStatus::Enabled);
+        assert_eq!(
+            status_for_interface_with(&source, "wg-work").unwrap(),
+            VpnStatus::SplitTunnel
+        );
+        assert_eq!(
+            status_for_interface_with(&source, "wg-home").unwrap(),
+            VpnStatus::Enabled
+        );
+        assert!(matches!(
+            status_for_interface_with(&source, "wg-missing"),
+            Err(VpnStatusError::InterfaceNotFound(name)) if name == "wg-missing"
+        ));
+
+        // the interface exists but is down
+        wg.flags = IFF_POINTOPOINT;
+        let source = MemorySource::new(vec![ethernet(true), wg, personal]);
+        assert_eq!(
+            status_for_interface_with(&source, "wg-work").unwrap(),
+            VpnStatus::Disabled
+        );
+    }
+
+    #[test]
+    fn test_route_to() {
+        let mut gateway = route("0.0.0.0/0", "eth0");
+        gateway.gateway = Some("192.168.1.1".parse().unwrap());
+        let source = MemorySource::new(vec![ethernet(true), tunnel(false)]).with_routes(vec![
+            gateway,
+            route("192.168.1.0/24", "eth0"),
+            route("10.20.0.0/16", "tun0"),
+        ]);
+
+        let egress = route_to_with(&source, "10.20.0.5".parse().unwrap());
+        assert_eq!(egress.interface(), Some("tun0"));
+        assert_eq!(egress.gateway(), None);
+        assert!(egress.tunneled());
+
+        let egress = route_to_with(&source, "140.82.121.4".parse().unwrap());
+        assert_eq!(egress.interface(), Some("eth0"));
+        assert_eq!(egress.gateway(), Some("192.168.1.1".parse().unwrap()));
+        assert!(!egress.tunneled());
+
+        let egress = route_to_with(&source, "192.168.1.10".parse().unwrap());
+        assert_eq!(egress.interface(), Some("lo"));
+
+        let egress = route_to_with(&source, "2001:db8::1".parse().unwrap());
+        assert_eq!(egress.route, None);
+        assert!(!egress.tunneled());
+
+        // excluded interfaces are not tunnels
+        let filtered = FilteredSource::new(&source, vec![], vec!["tun*".to_string()]);
+        let egress = route_to_with(&filtered, "10.20.0.5".parse().unwrap());
+        assert_eq!(egress.interface(), Some("tun0"));
+        assert!(!egress.tunneled());
+    }
+
+    #[test]
+    fn test_tunnel_chain() {
+        let wg = interface("wg0", IFF_UP | IFF_POINTOPOINT, "10.2.0.2/32", true);
+        let tun = interface("tun0", IFF_UP | IFF_POINTOPOINT, "10.8.0.2/24", false);
+        let peer = wireguard::Peer {
+            public_key: "bWFkZSB1cCBwdWJsaWMga2V5IGZvciB0ZXN0aW5nIQ==".to_string(),
+            endpoint: Some("198.51.100.7:51820".parse().unwrap()),
+            last_handshake: Some(now() - 42),
+            rx_bytes: 4096,
+            tx_bytes: 1024,
+        };
+        // the WireGuard endpoint is reached through the OpenVPN tunnel
+        let source = MemorySource::new(vec![ethernet(false), tun, wg])
+            .with_routes(vec![
+                route("0.0.0.0/0", "wg0"),
+                route("198.51.100.7/32", "tun0"),
+                route("0.0.0.0/0", "eth0"),
+            ])
+            .with_wireguard_peers(HashMap::from([("wg0".to_string(), vec![peer])]));
+        let config = Config {
+            output_format: Some("{status} {chain}".to_string()),
+            min_hops: Some(2),
+            ..Default::default()
+        };
+        let detection = detect_with(&source, &config).unwrap();
+        assert_eq!(detection.status, VpnStatus::Enabled);
+        assert_eq!(chain::hops(&detection.chain), 2);
+        let output = status_string_with(&source, config.clone(), true).unwrap();
+        assert_eq!(output, "enabled wg0 → tun0 → eth0");
+
+        let config = Config {
+            min_hops: Some(3),
+            ..config
+        };
+        let output = status_string_with(&source, config.clone(), true).unwrap();
+        assert_eq!(output, "short chain wg0 → tun0 → eth0");
+        // the tunnels still carry the traffic
+        assert!(vpn_enabled_with_config(&source, &config).unwrap());
+
+        // a single tunnel
+        let source = MemorySource::new(vec![ethernet(false), tunnel(true)])
+            .with_routes(vec![route("0.0.0.0/0", "tun0"), route("0.0.0.0/0", "eth0")]);
+        let detection = detect_with(&source, &Config::default()).unwrap();
+        assert_eq!(chain::format(&detection.chain), "tun0 → eth0");
+    }
+
+    #[test]
+    fn test_tunnel_policy() {
+        let source = MemorySource::new(vec![ethernet(true), tunnel(false)]).with_routes(vec![
+            route("0.0.0.0/0", "eth0"),
+            route("192.168.1.0/24", "eth0"),
+            route("10.20.0.0/16", "tun0"),
+        ]);
+        let config = Config {
+            must_tunnel: Some(vec!["10.20.0.0/16".to_string()]),
+            must_bypass: Some(vec!["192.168.1.20".to_string()]),
+            output_format: Some("{status} {violations}".to_string()),
+            ..Default::default()
+        };
+        let detection = detect_with(&source, &config).unwrap();
+        assert_eq!(detection.status, VpnStatus::SplitTunnel);
+        assert!(detection.violations.is_empty());
+
+        // only part of the internal network is tunneled, the printer is reached through the tunnel
+        let config = Config {
+            must_tunnel: Some(vec!["10.0.0.0/8".to_string()]),
+            must_bypass: Some(vec!["10.20.5.9".to_string()]),
+            ..config
+        };
+        let detection = detect_with(&source, &config).unwrap();
+        assert_eq!(detection.status, VpnStatus::Violation);
+        let violation = &detection.violations[0];
+        assert_eq!(violation.destination, "10.0.0.0/8");
+        assert_eq!(violation.requirement, policy::Requirement::Tunnel);
+        assert_eq!(violation.interface.as_deref(), Some("eth0"));
+        assert!(detection.violations.iter().any(|violation| {
+            violation.requirement == policy::Requirement::Bypass
+                && violation.interface.as_deref() == Some("tun0")
+        }));
+        let output = status_string_with(&source, config.clone(), true).unwrap();
+        assert_eq!(output, "violation 10.0.0.0/8, 10.20.5.9");
+        assert!(vpn_enabled_with_config(&source, &config).unwrap());
+
+        // the policy is not evaluated without any tunnel
+        let source = MemorySource::new(vec![ethernet(true)]);
+        let detection = detect_with(&source, &config).unwrap();
+        assert_eq!(detection.status, VpnStatus::Disabled);
+        assert!(detection.violations.is_empty());
+        assert!(!vpn_enabled_with_config(&source, &config).unwrap());
+
+        // nor while the client is still bringing up the tunnel
+        let openvpn = process::Client {
+            pid: 812,
+            name: "openvpn".to_string(),
+            kind: kind::TunnelKind::OpenVpn,
+            daemon: false,
+            interface: Some("tun0".to_string()),
+        };
+        let source = MemorySource::new(vec![ethernet(true)]).with_clients(vec![openvpn]);
+        let detection = detect_with(&source, &config).unwrap();
+        assert_eq!(detection.status, VpnStatus::Connecting);
+        assert!(detection.violations.is_empty());
+    }
+
+    #[test]
+    fn test_vpn_clients() {
+        let openconnect = process::Client {
+            pid: 4242,
+            name: "openconnect".to_string(),
+            kind: kind::TunnelKind::AnyConnect,
+            daemon: false,
+            interface: Some("tun0".to_string()),
+        };
+        let mullvad = process::Client {
+            pid: 1377,
+            name: "mullvad-daemon".to_string(),
+            kind: kind::TunnelKind::WireGuard,
+            daemon: true,
+            interface: None,
+        };
+        let config = Config {
+            output_format: Some("{status} {provider} {client}".to_string()),
+            ..Default::default()
+        };
+
+        // the client has not brought up the tunnel yet
+        let source = MemorySource::new(vec![ethernet(true)])
+            .with_clients(vec![openconnect.clone(), mullvad.clone()]);
+        let output = status_string_with(&source, config.clone(), true).unwrap();
+        assert_eq!(output, "connecting  openconnect, mullvad-daemon");
+
+        // daemons keep running while disconnected
+        let source = MemorySource::new(vec![ethernet(true)]).with_clients(vec![mullvad]);
+        assert_eq!(
+            detect_with(&source, &config).unwrap().status,
+            VpnStatus::Disabled
+        );
+
+        // the client tells the kind of the tunnel
+        let source = MemorySource::new(vec![ethernet(false), tunnel(true)])
+            .with_clients(vec![openconnect.clone()]);
+        let output = status_string_with(&source, config, true).unwrap();
+        assert_eq!(output, "enabled anyconnect openconnect");
+
+        // the tunnel of the client is up, but excluded
+        let source = MemorySource::new(vec![ethernet(true), tunnel(false)])
+            .with_clients(vec![openconnect.clone()]);
+        let config = Config {
+            exclude_interfaces: Some(vec!["tun*".to_string()]),
+            ..Default::default()
+        };
+        assert_eq!(
+            detect_with(&source, &config).unwrap().status,
+            VpnStatus::Disabled
+        );
+
+        // clients of other tunnels do not connect the checked one
+        let work = interface("wg-work", IFF_POINTOPOINT, "10.2.0.2/32", false);
+        let openvpn = process::Client {
+            pid: 812,
+            name: "openvpn".to_string(),
+            kind: kind::TunnelKind::OpenVpn,
+            daemon: false,
+            interface: None,
+        };
+        let source =
+            MemorySource::new(vec![ethernet(true), work]).with_clients(vec![openconnect, openvpn]);
+        assert_eq!(status_with(&source).unwrap(), VpnStatus::Connecting);
+        assert_eq!(
+            status_for_interface_with(&source, "wg-work").unwrap(),
+            VpnStatus::Disabled
+        );
+    }
+
+    #[test]
+    fn test_networkmanager_connections() {
+        let connection = |id: &str, state, interface: Option<&str>| networkmanager::Connection {
+            id: id.to_string(),
+            uuid: format!("uuid-{}", id),
+            kind: "wireguard".to_string(),
+            state,
+            interface: interface.map(str::to_string),
+        };
+        let config = Config {
+            output_format: Some("{status} {connection}".to_string()),
+            ..Default::default()
+        };
+
+        let source = MemorySource::new(vec![ethernet(true)]).with_connections(vec![connection(
+            "Office",
+            networkmanager::ConnectionState::Activating,
+            None,
+        )]);
+        let output = status_string_with(&source, config.clone(), true).unwrap();
+        assert_eq!(output, "connecting Office");
+
+        let source = MemorySource::new(vec![ethernet(false), tunnel(true)]).with_connections(vec![
+            connection("Office", networkmanager::ConnectionState::Failed, None),
+            connection(
+                "Home",
+                networkmanager::ConnectionState::Activated,
+                Some("tun0"),
+            ),
+        ]);
+        let output = status_string_with(&source, config, true).unwrap();
+        assert_eq!(output, "enabled Home");
+    }
+
+    #[test]
+    fn test_point_to_point_tunnels() {
+        // pppd links, e.g. of L2TP or SSTP connections
+        let source = MemorySource::new(vec![
+            ethernet(false),
+            interface("ppp0", IFF_UP | IFF_BROADCAST, "10.10.0.5/32", true),
+        ]);
+        assert_eq!(status_with(&source).unwrap(), VpnStatus::Enabled);
+
+        let source = MemorySource::new(vec![
+            ethernet(false),
+            interface("gre1", IFF_UP | IFF_POINTOPOINT, "172.16.0.2/30", true),
+        ]);
+        assert_eq!(status_with(&source).unwrap(), VpnStatus::Disabled);
+        let config = Config {
+            ip_tunnels: Some(true),
+            ..Default::default()
+        };
+        let detection = detect_with(&source, &config).unwrap();
+        assert_eq!(detection.status, VpnStatus::Enabled);
+        assert_eq!(detection.tunnels[0].kind, TunnelKind::IpTunnel);
+
+        // the tunnel functions agree with the status
+        let source = MemorySource::new(vec![
+            ethernet(false),
+            interface("ppp0", IFF_UP | IFF_BROADCAST, "10.10.0.5/32", true),
+            interface("gre1", IFF_UP | IFF_POINTOPOINT, "172.16.0.2/30", false),
+        ]);
+        assert_eq!(all_tunnel_names_with(&source), vec!["ppp0".to_string()]);
+        assert_eq!(tunnel_name_with(&source).unwrap(), "ppp0");
+        assert!(all_tunnel_addresses_with(&source).contains_key("ppp0"));
+        assert!(!all_tunnel_addresses_with(&source).contains_key("gre1"));
+        assert_eq!(
+            all_tunnel_names_with_config(&source, &config),
+            vec!["ppp0".to_string(), "gre1".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_trusted_networks() {
+        let mut eth = ethernet(true);
+        eth.gateway = Some(netdev::NetworkDevice {
+            mac_addr: "00:1a:2b:3c:4d:5e".parse().unwrap(),
+            ipv4: vec!["192.168.1.1".parse().unwrap()],
+            ipv6: vec![],
+        });
+        let mut wlan = interface("wlan0", IFF_UP | IFF_BROADCAST, "10.42.0.7/24", true);
+        wlan.gateway = None;
+        let mut gateway = route("0.0.0.0/0", "wlan0");
+        gateway.gateway = Some("10.42.0.1".parse().unwrap());
+        let wireless = MemorySource::new(vec![wlan])
+            .with_routes(vec![gateway])
+            .with_ssids(HashMap::from([("wlan0".to_string(), "Home".to_string())]));
+
+        let trusted = |patterns: &[&str]| Config {
+            trusted_networks: Some(patterns.iter().map(|p| p.to_string()).collect()),
+            ..Default::default()
+        };
+        let source = MemorySource::new(vec![eth.clone()]);
+        for pattern in ["00-1A-2B-3C-4D-5E", "192.168.1.0/24", "192.168.1.1"] {
+            let detection = detect_with(&source, &trusted(&[pattern])).unwrap();
+            assert_eq!(detection.status, VpnStatus::Trusted);
+            assert_eq!(detection.trusted, Some(pattern.to_string()));
+        }
+        let detection = detect_with(&wireless, &trusted(&["Office", "Ho*"])).unwrap();
+        assert_eq!(detection.status, VpnStatus::Trusted);
+        assert_eq!(detection.trusted, Some("Ho*".to_string()));
+        let output = status_string_with(&wireless, trusted(&["Home"]), true).unwrap();
+        assert_eq!(output, "trusted");
+
+        // other networks still need the VPN, which is not affected by trusting the network
+        let detection = detect_with(&wireless, &trusted(&["192.168.1.0/24"])).unwrap();
+        assert_eq!(detection.status, VpnStatus::Disabled);
+        let source = MemorySource::new(vec![eth, tunnel(false)])
+            .with_routes(vec![route("0.0.0.0/0", "tun0"), route("0.0.0.0/0", "eth0")]);
+        let detection = detect_with(&source, &trusted(&["192.168.1.1"])).unwrap();
+        assert_eq!(detection.status, VpnStatus::Enabled);
+        assert_eq!(detection.trusted, None);
+    }
+
+    #[test]
+    fn test_status_disabled() {
+        let source = MemorySource::new(vec![ethernet(true)]);
+        assert_eq!(status_with(&source).unwrap(), VpnStatus::Disabled);
+        assert!(!vpn_enabled_with(&source).unwrap());
+        assert!(all_tunnel_names_with(&source).is_empty());
+        assert!(all_tunnel_addresses_with(&source).is_empty());
+    }
+
+    #[test]
+    fn test_namespace_status() {
+        let source = namespace::read_proc(std::path::Path::new("fixtures/proc/4242")).unwrap();
+        assert_eq!(status_with(&source).unwrap(), VpnStatus::Enabled);
+        assert_eq!(tunnel_name_with(&source).unwrap(), "tun0");
+
+        let config = Config {
+            exclude_interfaces: Some(vec!["tun0".to_string()]),
+            ..Default::default()
+        };
+        assert_eq!(
+            detect_with(&source, &config).unwrap().status,
+            VpnStatus::Disabled
+        );
+    }
+
+    #[test]
+    fn test_status_offline() {
+        let mut down = ethernet(false);
+        down.flags = IFF_BROADCAST;
+        let source = MemorySource::new(vec![down]);
+        assert_eq!(status_with(&source).unwrap(), VpnStatus::Offline);
+        assert!(!vpn_enabled_with(&source).unwrap());
+        assert!(tunnel_address_with(&source).is_err());
+
+        // the link is up, but there is no default route
+        let source = MemorySource::new(vec![ethernet(false)]);
+        assert_eq!(status_with(&source).unwrap(), VpnStatus::NoGateway);
+        assert!(!vpn_enabled_with(&source).unwrap());
+        let output = status_string_with(&source, Config::default(), true).unwrap();
+        assert_eq!(output, "no gateway");
+
+        // a default route over a link without an address does not make the host online
+        let mut bare = ethernet(true);
+        bare.ipv4.clear();
+        bare.ipv6.clear();
+        let source = MemorySource::new(vec![bare]);
+        assert_eq!(status_with(&source).unwrap(), VpnStatus::Offline);
+    }
+
+    #[test]
+    fn test_no_interfaces() {
+        let source = MemorySource::default();
+        assert!(matches!(
+            status_with(&source),
+            Err(VpnStatusError::NoInterfaces)
+        ));
+        assert!(matches!(
+            tunnel_name_with(&source),
+            Err(VpnStatusError::NoInterfaces)
+        ));
+        assert!(matches!(
+            tunnel_address_with(&source),
+            Err(VpnStatusError::NoInterfaces)
+        ));
+    }
+
+    #[test]
+    fn test_captive_portal() {
+        let portal = connectivity::tests::serve("HTTP/1.1 302 Found\r\nLocation: /login\r\n\r\n");
+        let config = Config {
+            captive_portal_url: Some(portal),
+            ..Default::default()
+        };
+        let source = MemorySource::new(vec![ethernet(true)]);
+        let output = status_string_with(&source, config, true).unwrap();
+        assert_eq!(output, "captive portal");
+
+        let open = connectivity::tests::serve("HTTP/1.1 204 No Content\r\n\r\n");
+        let config = Config {
+            captive_portal_url: Some(open),
+            ..Default::default()
+        };
+        let detection = detect_with(&source, &config).unwrap();
+        assert_eq!(detection.status, VpnStatus::Disabled);
+
+        // the portal is not probed while the VPN is connected
+        let source = MemorySource::new(vec![ethernet(false), tunnel(true)]);
+        let config = Config {
+            captive_portal_url: Some("http://127.0.0.1:9/".to_string()),
+            ..Default::default()
+        };
+        let detection = detect_with(&source, &config).unwrap();
+        assert_eq!(detection.status, VpnStatus::Enabled);
+    }
 }
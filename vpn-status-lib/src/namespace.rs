@@ -0,0 +1,358 @@
+use crate::error::VpnStatusError;
+use crate::kind::{self, TunnelKind};
+use crate::routes::{self, AddressFamily, Route};
+use crate::rules::Rule;
+use crate::source::MemorySource;
+use crate::traffic::Counters;
+use log::debug;
+use netdev::ipnet::{Ipv4Net, Ipv6Net};
+use netdev::Interface;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::{Path, PathBuf};
+
+const IFF_UP: u32 = 0x1;
+const IFF_LOOPBACK: u32 = 0x8;
+const IFF_POINTOPOINT: u32 = 0x10;
+const IFF_RUNNING: u32 = 0x40;
+
+/// Network namespace to determine the VPN status in, e.g. of a container
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Namespace {
+    /// namespace of a process
+    Pid(u32),
+    /// namespace file, e.g. `/run/netns/<name>` or `/proc/<pid>/ns/net`
+    Path(PathBuf),
+}
+
+impl Namespace {
+    /// Capture the network state of the namespace.
+    ///
+    /// The namespace is entered when privileged, which requires `CAP_SYS_ADMIN`. Otherwise the
+    /// state of a process namespace is read from `/proc/<pid>/net`, which only lists the main
+    /// routing table.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # use vpn_status_lib::namespace::Namespace;
+    /// let source = Namespace::Pid(4242).capture().unwrap();
+    /// println!("{}", vpn_status_lib::status_with(&source).unwrap());
+    /// ```
+    pub fn capture(&self) -> Result<MemorySource, VpnStatusError> {
+        match self.run(snapshot).and_then(|source| source) {
+            Ok(source) => Ok(source),
+            Err(error) => match self {
+                Self::Pid(pid) => {
+                    debug!("{}, reading procfs instead", error);
+                    read_proc(&Path::new("/proc").join(pid.to_string()))
+                }
+                Self::Path(_) => Err(error),
+            },
+        }
+    }
+
+    /// Run a function on a thread moved into the namespace, which requires `CAP_SYS_ADMIN`.
+    ///
+    /// Only sockets follow the namespace of the thread, procfs and sysfs still show the
+    /// namespace of the process.
+    pub fn run<T, F>(&self, function: F) -> Result<T, VpnStatusError>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let path = match self {
+            Self::Pid(pid) => PathBuf::from(format!("/proc/{}/ns/net", pid)),
+            Self::Path(path) => path.clone(),
+        };
+        enter(&path, function)
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn enter<T, F>(path: &Path, function: F) -> Result<T, VpnStatusError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    use std::os::fd::AsRawFd;
+
+    let backend = format!("network namespace {}", path.display());
+    let file =
+        std::fs::File::open(path).map_err(|error| VpnStatusError::backend(&backend, error))?;
+    // only the calling thread changes its namespace
+    let thread = std::thread::spawn(move || {
+        if unsafe { libc::setns(file.as_raw_fd(), libc::CLONE_NEWNET) } != 0 {
+            return Err(VpnStatusError::backend(
+                &backend,
+                std::io::Error::last_os_error(),
+            ));
+        }
+        Ok(function())
+    });
+    thread.join().unwrap_or_else(|_| {
+        Err(VpnStatusError::Backend {
+            backend: path.display().to_string(),
+            source: std::io::Error::other("namespace thread panicked"),
+        })
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn enter<T, F>(path: &Path, _function: F) -> Result<T, VpnStatusError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    Err(VpnStatusError::Backend {
+        backend: path.display().to_string(),
+        source: std::io::Error::from(std::io::ErrorKind::Unsupported),
+    })
+}
+
+/// Capture the network state of the namespace of the current thread
+#[cfg(target_os = "linux")]
+fn snapshot() -> Result<MemorySource, VpnStatusError> {
+    use crate::source::{InterfaceSource, NetdevSource};
+
+    let source = NetdevSource;
+    // procfs and sysfs still show the namespace of the process, only netlink can be used
+    let routes = source.read_routes()?;
+    let rules = source.rules();
+    let mut interfaces = netdev::get_interfaces();
+    mark_default(&mut interfaces, &routes, &rules);
+    let peers = interfaces
+        .iter()
+        .filter(|interface| interface.is_tun())
+        .map(|interface| {
+            let peers = source.wireguard_peers(&interface.name);
+            (interface.name.clone(), peers)
+        })
+        .filter(|(_, peers)| !peers.is_empty())
+        .collect();
+    Ok(MemorySource::new(interfaces)
+        .with_routes(routes)
+        .with_rules(rules)
+        .with_wireguard_peers(peers)
+        .with_ipsec(source.ipsec_policies(), source.ipsec_states()))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn snapshot() -> Result<MemorySource, VpnStatusError> {
+    unreachable!("namespaces are only entered on linux")
+}
+
+/// Read the network state of a process from its procfs directory, e.g. `/proc/<pid>`.
+///
+/// Interface flags are read from the sysfs mounted in the root of the process when it is
+/// accessible, otherwise they are guessed from the addresses and interface names.
+pub fn read_proc(process: &Path) -> Result<MemorySource, VpnStatusError> {
+    let net = process.join("net");
+    let dev = std::fs::read_to_string(net.join("dev"))
+        .map_err(|error| VpnStatusError::backend(&net.display().to_string(), error))?;
+    let read = |file: &str| std::fs::read_to_string(net.join(file)).unwrap_or_default();
+    let routes = routes::read_proc_routes(&net);
+    let local = parse_fib_trie(&read("fib_trie"));
+    let ipv6 = parse_if_inet6(&read("if_inet6"));
+    let class_net = process.join("root/sys/class/net");
+
+    let mut interfaces = vec![];
+    let mut links = HashMap::new();
+    let mut counters = HashMap::new();
+    for (position, (name, interface_counters)) in parse_dev(&dev).into_iter().enumerate() {
+        let mut interface = Interface::dummy();
+        interface.name = name.clone();
+        interface.ipv4 = local
+            .iter()
+            .filter_map(|address| ipv4_net(address, &name, &routes))
+            .collect();
+        interface.ipv6 = ipv6
+            .iter()
+            .filter(|(_, interface_name, _)| *interface_name == name)
+            .map(|(_, _, net)| *net)
+            .collect();
+        let sysfs = |file: &str| std::fs::read_to_string(class_net.join(&name).join(file));
+        interface.index = sysfs("ifindex")
+            .ok()
+            .and_then(|index| index.trim().parse().ok())
+            .or_else(|| {
+                ipv6.iter()
+                    .find(|(_, interface_name, _)| *interface_name == name)
+                    .map(|(index, _, _)| *index)
+            })
+            .unwrap_or(position as u32 + 1);
+        interface.flags = match sysfs("flags") {
+            Ok(flags) => {
+                u32::from_str_radix(flags.trim().trim_start_matches("0x"), 16).unwrap_or_default()
+            }
+            Err(_) => guess_flags(&interface, &routes),
+        };
+
+        if let Some(link) = kind::read_link_info(&class_net, &name) {
+            links.insert(name.clone(), link);
+        }
+        counters.insert(name, interface_counters);
+        interfaces.push(interface);
+    }
+
+    mark_default(&mut interfaces, &routes, &[]);
+    Ok(MemorySource::new(interfaces)
+        .with_routes(routes)
+        .with_links(links)
+        .with_counters(counters))
+}
+
+/// Mark the interface carrying the default route
+fn mark_default(interfaces: &mut [Interface], routes: &[Route], rules: &[Rule]) {
+    let default = routes::default_route(routes, rules, AddressFamily::Ipv4)
+        .or_else(|| routes::default_route(routes, rules, AddressFamily::Ipv6))
+        .map(|matched| matched.route.interface);
+    for interface in interfaces {
+        interface.default = Some(&interface.name) == default.as_ref();
+    }
+}
+
+/// Guess the flags of an interface without sysfs, tunnels are only recognised by their name
+fn guess_flags(interface: &Interface, routes: &[Route]) -> u32 {
+    if interface.name == "lo" {
+        return IFF_UP | IFF_RUNNING | IFF_LOOPBACK;
+    }
+    let mut flags = 0;
+    // routes of interfaces which are down are removed
+    if !interface.ipv4.is_empty()
+        || !interface.ipv6.is_empty()
+        || routes.iter().any(|route| route.interface == interface.name)
+    {
+        flags |= IFF_UP | IFF_RUNNING;
+    }
+    if kind::classify(&interface.name, None, &HashMap::new()) != TunnelKind::Unknown {
+        flags |= IFF_POINTOPOINT;
+    }
+    flags
+}
+
+/// Get the network of a local address on an interface from its connected route
+fn ipv4_net(address: &Ipv4Addr, name: &str, routes: &[Route]) -> Option<Ipv4Net> {
+    if address.is_loopback() {
+        return (name == "lo").then(|| Ipv4Net::new(*address, 8).ok())?;
+    }
+    // the most specific connected route belongs to the interface of the address
+    let route = routes
+        .iter()
+        .filter(|route| {
+            route.gateway.is_none() && route.destination.contains(&IpAddr::V4(*address))
+        })
+        .max_by_key(|route| route.destination.prefix_len())
+        .filter(|route| route.interface == name)?;
+    match route.destination.prefix_len() {
+        // a default route over the interface tells nothing about its network
+        0 => Ipv4Net::new(*address, 32).ok(),
+        prefix => Ipv4Net::new(*address, prefix).ok(),
+    }
+}
+
+/// Parse the interface names and byte counters from `/proc/net/dev`
+fn parse_dev(content: &str) -> Vec<(String, Counters)> {
+    // skip the two header lines
+    content
+        .lines()
+        .skip(2)
+        .filter_map(|line| {
+            let (name, statistics) = line.split_once(':')?;
+            let statistics: Vec<u64> = statistics
+                .split_whitespace()
+                .filter_map(|value| value.parse().ok())
+                .collect();
+            Some((
+                name.trim().to_string(),
+                Counters {
+                    rx_bytes: *statistics.first()?,
+                    tx_bytes: *statistics.get(8)?,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Parse the local IPv4 addresses from `/proc/net/fib_trie`
+fn parse_fib_trie(content: &str) -> Vec<Ipv4Addr> {
+    let mut addresses = vec![];
+    let mut last = None;
+    for line in content.lines().map(str::trim) {
+        if let Some(address) = line.strip_prefix("|-- ") {
+            last = address.parse().ok();
+        } else if line.starts_with("/32 host LOCAL") {
+            if let Some(address) = last.take() {
+                if !addresses.contains(&address) {
+                    addresses.push(address);
+                }
+            }
+        }
+    }
+    addresses
+}
+
+/// Parse the IPv6 addresses from `/proc/net/if_inet6`
+fn parse_if_inet6(content: &str) -> Vec<(u32, String, Ipv6Net)> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 6 {
+                return None;
+            }
+            let address = Ipv6Addr::from(u128::from_str_radix(fields[0], 16).ok()?);
+            let index = u32::from_str_radix(fields[1], 16).ok()?;
+            let prefix = u8::from_str_radix(fields[2], 16).ok()?;
+            let net = Ipv6Net::new(address, prefix).ok()?;
+            Some((index, fields[5].to_string(), net))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::InterfaceSource;
+    use netdev::ipnet::IpNet;
+
+    #[test]
+    fn proc_fixture() {
+        let source = read_proc(Path::new("fixtures/proc/4242")).unwrap();
+        let interfaces = source.interfaces();
+        let names: Vec<&str> = interfaces.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(names, vec!["lo", "eth0", "tun0"]);
+
+        let tun = source.default_interface().unwrap();
+        assert_eq!(tun.name, "tun0");
+        assert_eq!(tun.index, 5);
+        assert!(tun.is_tun());
+        assert_eq!(tun.ipv4, vec!["10.8.0.2/24".parse::<Ipv4Net>().unwrap()]);
+
+        let eth = &interfaces[1];
+        assert!(!eth.is_tun());
+        assert_eq!(eth.ipv4, vec!["172.17.0.2/16".parse::<Ipv4Net>().unwrap()]);
+        assert_eq!(eth.ipv6.len(), 1);
+        assert!(interfaces[0].is_loopback());
+
+        assert_eq!(source.routes().len(), 5);
+        assert_eq!(source.counters("tun0").unwrap().tx_bytes, 262144);
+        assert!(source.link_info("tun0").unwrap().tun_driver);
+        assert!(matches!(
+            read_proc(Path::new("fixtures/proc/missing")),
+            Err(VpnStatusError::Backend { .. })
+        ));
+    }
+
+    #[test]
+    fn flags_without_sysfs() {
+        let routes = vec![Route::new(IpNet::V4("10.8.0.0/24".parse().unwrap()), "wg0")];
+        let mut interface = Interface::dummy();
+        interface.name = "wg0".to_string();
+        assert_eq!(
+            guess_flags(&interface, &routes),
+            IFF_UP | IFF_RUNNING | IFF_POINTOPOINT
+        );
+        interface.name = "eth0".to_string();
+        assert_eq!(guess_flags(&interface, &routes), 0);
+    }
+}
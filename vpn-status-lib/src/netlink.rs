@@ -0,0 +1,753 @@
+//! Minimal netlink client used to read the kernel networking state on Linux.
+
+use crate::ipsec::{Policy, PolicyAction, PolicyDirection, State, Template};
+use crate::routes::{AddressFamily, Route, RouteKind, RT_TABLE_MAIN};
+use crate::rules::{Rule, RuleAction};
+use crate::wireguard::{self, Peer};
+use netdev::ipnet::IpNet;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+const NLMSG_HDRLEN: usize = 16;
+const NLMSG_ERROR: u16 = 2;
+const NLMSG_DONE: u16 = 3;
+const NLM_F_REQUEST: u16 = 0x01;
+const NLM_F_ACK: u16 = 0x04;
+pub(crate) const NLM_F_DUMP: u16 = 0x300;
+const NLA_TYPE_MASK: u16 = 0x3fff;
+
+const RTM_GETROUTE: u16 = 26;
+const RTM_GETRULE: u16 = 34;
+const RTM_F_CLONED: u32 = 0x200;
+const RTN_UNICAST: u8 = 1;
+const RTN_BLACKHOLE: u8 = 6;
+const RTN_UNREACHABLE: u8 = 7;
+const RTN_PROHIBIT: u8 = 8;
+const RTN_THROW: u8 = 9;
+const RTA_DST: u16 = 1;
+const RTA_OIF: u16 = 4;
+const RTA_GATEWAY: u16 = 5;
+const RTA_PRIORITY: u16 = 6;
+const RTA_MULTIPATH: u16 = 9;
+const RTA_TABLE: u16 = 15;
+
+const FRA_DST: u16 = 1;
+const FRA_SRC: u16 = 2;
+const FRA_IIFNAME: u16 = 3;
+const FRA_PRIORITY: u16 = 6;
+const FRA_FWMARK: u16 = 10;
+const FRA_SUPPRESS_PREFIXLEN: u16 = 14;
+const FRA_TABLE: u16 = 15;
+const FRA_FWMASK: u16 = 16;
+const FRA_OIFNAME: u16 = 17;
+const FR_ACT_TO_TBL: u8 = 1;
+const FR_ACT_BLACKHOLE: u8 = 6;
+const FR_ACT_UNREACHABLE: u8 = 7;
+const FR_ACT_PROHIBIT: u8 = 8;
+const FIB_RULE_INVERT: u32 = 0x2;
+
+const XFRM_MSG_GETSA: u16 = 0x12;
+const XFRM_MSG_GETPOLICY: u16 = 0x15;
+const XFRMA_TMPL: u16 = 5;
+const XFRM_MODE_TUNNEL: u8 = 1;
+const XFRM_POLICY_BLOCK: u8 = 1;
+/// size of struct xfrm_selector
+const XFRM_SELECTOR_LEN: usize = 56;
+/// size of struct xfrm_userpolicy_info
+const XFRM_POLICY_INFO_LEN: usize = 168;
+/// size of struct xfrm_usersa_info
+const XFRM_SA_INFO_LEN: usize = 224;
+/// size of struct xfrm_user_tmpl
+const XFRM_TEMPLATE_LEN: usize = 64;
+
+const GENL_ID_CTRL: u16 = 0x10;
+const CTRL_CMD_GETFAMILY: u8 = 3;
+const CTRL_ATTR_FAMILY_ID: u16 = 1;
+const CTRL_ATTR_FAMILY_NAME: u16 = 2;
+
+const WG_GENL_NAME: &str = "wireguard";
+const WG_GENL_VERSION: u8 = 1;
+const WG_CMD_GET_DEVICE: u8 = 0;
+const WGDEVICE_A_IFNAME: u16 = 2;
+const WGDEVICE_A_PEERS: u16 = 8;
+const WGPEER_A_PUBLIC_KEY: u16 = 1;
+const WGPEER_A_ENDPOINT: u16 = 4;
+const WGPEER_A_LAST_HANDSHAKE_TIME: u16 = 6;
+const WGPEER_A_RX_BYTES: u16 = 7;
+const WGPEER_A_TX_BYTES: u16 = 8;
+
+const NL80211_GENL_NAME: &str = "nl80211";
+const NL80211_CMD_GET_INTERFACE: u8 = 5;
+const NL80211_ATTR_IFINDEX: u16 = 3;
+const NL80211_ATTR_SSID: u16 = 52;
+
+/// Netlink socket, closed on drop
+struct Socket(libc::c_int);
+
+impl Drop for Socket {
+    fn drop(&mut self) {
+        unsafe { libc::close(self.0) };
+    }
+}
+
+const fn align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+/// Send a request and collect the response payloads until the kernel signals the end of it
+pub(crate) fn request(
+    protocol: libc::c_int,
+    msg_type: u16,
+    flags: u16,
+    payload: &[u8],
+) -> io::Result<Vec<Vec<u8>>> {
+    let fd = unsafe {
+        libc::socket(
+            libc::AF_NETLINK,
+            libc::SOCK_RAW | libc::SOCK_CLOEXEC,
+            protocol,
+        )
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let socket = Socket(fd);
+
+    let len = NLMSG_HDRLEN + payload.len();
+    let mut buf = Vec::with_capacity(align(len));
+    buf.extend_from_slice(&(len as u32).to_ne_bytes());
+    buf.extend_from_slice(&msg_type.to_ne_bytes());
+    buf.extend_from_slice(&(flags | NLM_F_REQUEST | NLM_F_ACK).to_ne_bytes());
+    buf.extend_from_slice(&1u32.to_ne_bytes());
+    buf.extend_from_slice(&0u32.to_ne_bytes());
+    buf.extend_from_slice(payload);
+
+    let mut address: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+    address.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+    let sent = unsafe {
+        libc::sendto(
+            socket.0,
+            buf.as_ptr() as *const libc::c_void,
+            buf.len(),
+            0,
+            &address as *const libc::sockaddr_nl as *const libc::sockaddr,
+            std::mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+        )
+    };
+    if sent < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut messages = vec![];
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let received = unsafe {
+            libc::recv(
+                socket.0,
+                buf.as_mut_ptr() as *mut libc::c_void,
+                buf.len(),
+                0,
+            )
+        };
+        if received < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut data = &buf[..received as usize];
+        while data.len() >= NLMSG_HDRLEN {
+            let len = u32::from_ne_bytes([data[0], data[1], data[2], data[3]]) as usize;
+            let msg_type = u16::from_ne_bytes([data[4], data[5]]);
+            if len < NLMSG_HDRLEN || len > data.len() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "truncated netlink message",
+                ));
+            }
+            match msg_type {
+                NLMSG_DONE => return Ok(messages),
+                NLMSG_ERROR => {
+                    let code = data
+                        .get(NLMSG_HDRLEN..NLMSG_HDRLEN + 4)
+                        .map(|code| i32::from_ne_bytes([code[0], code[1], code[2], code[3]]))
+                        .unwrap_or_default();
+                    if code == 0 {
+                        return Ok(messages);
+                    }
+                    return Err(io::Error::from_raw_os_error(-code));
+                }
+                _ => messages.push(data[NLMSG_HDRLEN..len].to_vec()),
+            }
+            data = &data[align(len).min(data.len())..];
+        }
+    }
+}
+
+/// Split a buffer into netlink attributes
+pub(crate) fn attributes(mut data: &[u8]) -> Vec<(u16, &[u8])> {
+    let mut attributes = vec![];
+    while data.len() >= 4 {
+        let len = u16::from_ne_bytes([data[0], data[1]]) as usize;
+        let kind = u16::from_ne_bytes([data[2], data[3]]) & NLA_TYPE_MASK;
+        if len < 4 || len > data.len() {
+            break;
+        }
+        attributes.push((kind, &data[4..len]));
+        data = &data[align(len).min(data.len())..];
+    }
+    attributes
+}
+
+/// Append a netlink attribute to a buffer
+pub(crate) fn push_attribute(buf: &mut Vec<u8>, kind: u16, value: &[u8]) {
+    let len = 4 + value.len();
+    buf.extend_from_slice(&(len as u16).to_ne_bytes());
+    buf.extend_from_slice(&kind.to_ne_bytes());
+    buf.extend_from_slice(value);
+    buf.resize(buf.len() + align(len) - len, 0);
+}
+
+pub(crate) fn attribute_u32(value: &[u8]) -> Option<u32> {
+    Some(u32::from_ne_bytes(value.get(..4)?.try_into().ok()?))
+}
+
+pub(crate) fn attribute_u64(value: &[u8]) -> Option<u64> {
+    Some(u64::from_ne_bytes(value.get(..8)?.try_into().ok()?))
+}
+
+pub(crate) fn attribute_string(value: &[u8]) -> String {
+    let end = value.iter().position(|b| *b == 0).unwrap_or(value.len());
+    String::from_utf8_lossy(&value[..end]).into_owned()
+}
+
+pub(crate) fn attribute_address(value: &[u8]) -> Option<IpAddr> {
+    match value.len() {
+        4 => Some(IpAddr::V4(Ipv4Addr::from(<[u8; 4]>::try_from(value).ok()?))),
+        16 => Some(IpAddr::V6(Ipv6Addr::from(
+            <[u8; 16]>::try_from(value).ok()?,
+        ))),
+        _ => None,
+    }
+}
+
+fn family_number(family: AddressFamily) -> u8 {
+    match family {
+        AddressFamily::Ipv4 => libc::AF_INET as u8,
+        AddressFamily::Ipv6 => libc::AF_INET6 as u8,
+    }
+}
+
+fn interface_name(index: u32) -> Option<String> {
+    let mut name = [0 as libc::c_char; libc::IF_NAMESIZE];
+    let result = unsafe { libc::if_indextoname(index, name.as_mut_ptr()) };
+    if result.is_null() {
+        return None;
+    }
+    let name = unsafe { std::ffi::CStr::from_ptr(name.as_ptr()) };
+    Some(name.to_string_lossy().into_owned())
+}
+
+/// Read the unicast and rejecting routes of all routing tables
+pub(crate) fn read_routes(family: AddressFamily) -> io::Result<Vec<Route>> {
+    // struct rtmsg
+    let mut header = [0u8; 12];
+    header[0] = family_number(family);
+    let messages = request(libc::NETLINK_ROUTE, RTM_GETROUTE, NLM_F_DUMP, &header)?;
+    Ok(messages
+        .iter()
+        .filter_map(|message| parse_route(family, message))
+        .collect())
+}
+
+fn parse_route(family: AddressFamily, payload: &[u8]) -> Option<Route> {
+    let header = payload.get(..12)?;
+    let (dst_len, table, route_type) = (header[1], header[4], header[7]);
+    let flags = u32::from_ne_bytes(header[8..12].try_into().ok()?);
+    let kind = match route_type {
+        RTN_UNICAST => RouteKind::Unicast,
+        RTN_BLACKHOLE => RouteKind::Blackhole,
+        RTN_UNREACHABLE => RouteKind::Unreachable,
+        RTN_PROHIBIT => RouteKind::Prohibit,
+        RTN_THROW => RouteKind::Throw,
+        _ => return None,
+    };
+    if flags & RTM_F_CLONED != 0 {
+        return None;
+    }
+
+    let mut route = Route::new(IpNet::new(family.unspecified(), 0).ok()?, "");
+    route.table = u32::from(table);
+    route.kind = kind;
+    let mut destination = family.unspecified();
+    let mut index = None;
+    for (kind, value) in attributes(&payload[12..]) {
+        match kind {
+            RTA_DST => destination = attribute_address(value)?,
+            RTA_OIF => index = attribute_u32(value),
+            RTA_GATEWAY => route.gateway = attribute_address(value),
+            RTA_PRIORITY => route.metric = attribute_u32(value).unwrap_or_default(),
+            RTA_TABLE => route.table = attribute_u32(value).unwrap_or(route.table),
+            RTA_MULTIPATH => {
+                // struct rtnexthop, use the first hop only
+                let hop = value.get(..8)?;
+                index = attribute_u32(&hop[4..8]);
+                let len = usize::from(u16::from_ne_bytes([hop[0], hop[1]]));
+                if let Some(hop_attributes) = value.get(8..len) {
+                    for (kind, value) in attributes(hop_attributes) {
+                        if kind == RTA_GATEWAY {
+                            route.gateway = attribute_address(value);
+                        }
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    route.destination = IpNet::new(destination, dst_len).ok()?.trunc();
+    // only unicast routes forward traffic to an interface
+    if kind == RouteKind::Unicast {
+        route.interface = interface_name(index?)?;
+    }
+    Some(route)
+}
+
+/// Read the routing policy rules
+pub(crate) fn read_rules(family: AddressFamily) -> io::Result<Vec<Rule>> {
+    // struct fib_rule_hdr
+    let mut header = [0u8; 12];
+    header[0] = family_number(family);
+    let messages = request(libc::NETLINK_ROUTE, RTM_GETRULE, NLM_F_DUMP, &header)?;
+    Ok(messages
+        .iter()
+        .filter_map(|message| parse_rule(family, message))
+        .collect())
+}
+
+fn parse_rule(family: AddressFamily, payload: &[u8]) -> Option<Rule> {
+    let header = payload.get(..12)?;
+    let (dst_len, src_len, table, action) = (header[1], header[2], header[4], header[7]);
+    let flags = u32::from_ne_bytes(header[8..12].try_into().ok()?);
+
+    let mut rule = Rule::new(family, 0, RuleAction::Lookup(u32::from(table)));
+    rule.invert = flags & FIB_RULE_INVERT != 0;
+    let mut table = u32::from(table);
+    for (kind, value) in attributes(&payload[12..]) {
+        match kind {
+            FRA_DST => rule.destination = IpNet::new(attribute_address(value)?, dst_len).ok(),
+            FRA_SRC => rule.source = IpNet::new(attribute_address(value)?, src_len).ok(),
+            FRA_IIFNAME => rule.input_interface = Some(attribute_string(value)),
+            FRA_OIFNAME => rule.output_interface = Some(attribute_string(value)),
+            FRA_PRIORITY => rule.priority = attribute_u32(value).unwrap_or_default(),
+            FRA_FWMARK => rule.fwmark = attribute_u32(value),
+            FRA_FWMASK => rule.fwmask = attribute_u32(value),
+            FRA_TABLE => table = attribute_u32(value).unwrap_or(table),
+            FRA_SUPPRESS_PREFIXLEN => {
+                // -1 means the option is not set
+                rule.suppress_prefix_length =
+                    attribute_u32(value).and_then(|len| u8::try_from(len).ok());
+            }
+            _ => (),
+        }
+    }
+    rule.action = match action {
+        FR_ACT_TO_TBL => RuleAction::Lookup(if table == 0 { RT_TABLE_MAIN } else { table }),
+        FR_ACT_BLACKHOLE | FR_ACT_UNREACHABLE | FR_ACT_PROHIBIT => RuleAction::Block,
+        // goto and nop rules do not select a route
+        _ => return None,
+    };
+    Some(rule)
+}
+
+/// Read an `xfrm_address_t` of the given address family
+fn xfrm_address(family: u16, value: &[u8]) -> Option<IpAddr> {
+    match i32::from(family) {
+        libc::AF_INET => attribute_address(value.get(..4)?),
+        libc::AF_INET6 => attribute_address(value.get(..16)?),
+        _ => None,
+    }
+}
+
+/// Read the IPsec policies of the kernel security policy database
+pub(crate) fn read_xfrm_policies() -> io::Result<Vec<Policy>> {
+    // struct xfrm_userpolicy_id
+    let header = [0u8; XFRM_SELECTOR_LEN + 8];
+    let messages = request(libc::NETLINK_XFRM, XFRM_MSG_GETPOLICY, NLM_F_DUMP, &header)?;
+    Ok(messages
+        .iter()
+        .filter_map(|message| parse_xfrm_policy(message))
+        .collect())
+}
+
+fn parse_xfrm_policy(payload: &[u8]) -> Option<Policy> {
+    let info = payload.get(..XFRM_POLICY_INFO_LEN)?;
+    // struct xfrm_selector
+    let family = u16::from_ne_bytes([info[40], info[41]]);
+    let (dst_len, src_len) = (info[42], info[43]);
+    let destination = IpNet::new(xfrm_address(family, &info[0..16])?, dst_len).ok()?;
+    let source = IpNet::new(xfrm_address(family, &info[16..32])?, src_len).ok()?;
+
+    let direction = match info[160] {
+        0 => PolicyDirection::In,
+        1 => PolicyDirection::Out,
+        2 => PolicyDirection::Forward,
+        // per socket policies
+        _ => return None,
+    };
+    let mut policy = Policy::new(direction, source.trunc(), destination.trunc());
+    policy.priority = attribute_u32(&info[152..156])?;
+    policy.action = match info[161] {
+        XFRM_POLICY_BLOCK => PolicyAction::Block,
+        _ => PolicyAction::Allow,
+    };
+    for (kind, value) in attributes(&payload[XFRM_POLICY_INFO_LEN..]) {
+        if kind != XFRMA_TMPL {
+            continue;
+        }
+        for template in value.chunks_exact(XFRM_TEMPLATE_LEN) {
+            let family = u16::from_ne_bytes([template[24], template[25]]);
+            policy.templates.push(Template {
+                destination: xfrm_address(family, &template[0..16])?,
+                reqid: attribute_u32(&template[44..48])?,
+                tunnel: template[48] == XFRM_MODE_TUNNEL,
+            });
+        }
+    }
+    Some(policy)
+}
+
+/// Read the IPsec states of the kernel security association database
+pub(crate) fn read_xfrm_states() -> io::Result<Vec<State>> {
+    // struct xfrm_usersa_id
+    let header = [0u8; 24];
+    let messages = request(libc::NETLINK_XFRM, XFRM_MSG_GETSA, NLM_F_DUMP, &header)?;
+    Ok(messages
+        .iter()
+        .filter_map(|message| parse_xfrm_state(message))
+        .collect())
+}
+
+fn parse_xfrm_state(payload: &[u8]) -> Option<State> {
+    let info = payload.get(..XFRM_SA_INFO_LEN)?;
+    let family = u16::from_ne_bytes([info[212], info[213]]);
+    Some(State {
+        source: xfrm_address(family, &info[80..96])?,
+        destination: xfrm_address(family, &info[56..72])?,
+        spi: u32::from_be_bytes(info[72..76].try_into().ok()?),
+        reqid: attribute_u32(&info[208..212])?,
+        tunnel: info[214] == XFRM_MODE_TUNNEL,
+    })
+}
+
+/// Resolve the id of a generic netlink family
+fn genl_family_id(name: &str) -> io::Result<u16> {
+    let mut payload = vec![CTRL_CMD_GETFAMILY, 1, 0, 0];
+    push_attribute(
+        &mut payload,
+        CTRL_ATTR_FAMILY_NAME,
+        format!("{}\0", name).as_bytes(),
+    );
+    let messages = request(libc::NETLINK_GENERIC, GENL_ID_CTRL, 0, &payload)?;
+    messages
+        .iter()
+        .filter_map(|message| {
+            attributes(message.get(4..)?)
+                .into_iter()
+                .find(|(kind, _)| *kind == CTRL_ATTR_FAMILY_ID)
+        })
+        .find_map(|(_, value)| Some(u16::from_ne_bytes(value.get(..2)?.try_into().ok()?)))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "generic netlink family not found"))
+}
+
+/// Read the peers of a WireGuard interface
+pub(crate) fn read_wireguard_peers(interface: &str) -> io::Result<Vec<Peer>> {
+    let family = genl_family_id(WG_GENL_NAME)?;
+    let mut payload = vec![WG_CMD_GET_DEVICE, WG_GENL_VERSION, 0, 0];
+    push_attribute(
+        &mut payload,
+        WGDEVICE_A_IFNAME,
+        format!("{}\0", interface).as_bytes(),
+    );
+    let messages = request(libc::NETLINK_GENERIC, family, NLM_F_DUMP, &payload)?;
+    // devices with many allowed ips are split over several messages, which repeat the
+    // public key of the peer being continued
+    let mut peers: Vec<Peer> = vec![];
+    for peer in messages
+        .iter()
+        .flat_map(|message| parse_wireguard_device(message))
+    {
+        if !peers
+            .iter()
+            .any(|known| known.public_key == peer.public_key)
+        {
+            peers.push(peer);
+        }
+    }
+    Ok(peers)
+}
+
+fn parse_wireguard_device(payload: &[u8]) -> Vec<Peer> {
+    let Some(device) = payload.get(4..) else {
+        return vec![];
+    };
+    attributes(device)
+        .into_iter()
+        .filter(|(kind, _)| *kind == WGDEVICE_A_PEERS)
+        .flat_map(|(_, peers)| attributes(peers))
+        .filter_map(|(_, peer)| parse_wireguard_peer(peer))
+        .collect()
+}
+
+fn parse_wireguard_peer(payload: &[u8]) -> Option<Peer> {
+    let mut peer = Peer {
+        public_key: String::new(),
+        endpoint: None,
+        last_handshake: None,
+        rx_bytes: 0,
+        tx_bytes: 0,
+    };
+    for (kind, value) in attributes(payload) {
+        match kind {
+            WGPEER_A_PUBLIC_KEY => peer.public_key = wireguard::encode_key(value),
+            WGPEER_A_ENDPOINT => peer.endpoint = parse_sockaddr(value),
+            WGPEER_A_LAST_HANDSHAKE_TIME => {
+                // struct __kernel_timespec, zero if there was no handshake yet
+                let seconds = attribute_u64(value)?;
+                peer.last_handshake = (seconds != 0).then_some(seconds);
+            }
+            WGPEER_A_RX_BYTES => peer.rx_bytes = attribute_u64(value)?,
+            WGPEER_A_TX_BYTES => peer.tx_bytes = attribute_u64(value)?,
+            _ => (),
+        }
+    }
+    (!peer.public_key.is_empty()).then_some(peer)
+}
+
+/// Read the SSID a wireless interface is connected to, none while it is not associated
+pub(crate) fn read_ssid(interface: &str) -> io::Result<Option<String>> {
+    let name = std::ffi::CString::new(interface)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+    let index = unsafe { libc::if_nametoindex(name.as_ptr()) };
+    if index == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let family = genl_family_id(NL80211_GENL_NAME)?;
+    let mut payload = vec![NL80211_CMD_GET_INTERFACE, 0, 0, 0];
+    push_attribute(&mut payload, NL80211_ATTR_IFINDEX, &index.to_ne_bytes());
+    let messages = request(libc::NETLINK_GENERIC, family, 0, &payload)?;
+    Ok(messages
+        .iter()
+        .find_map(|message| parse_nl80211_interface(message)))
+}
+
+fn parse_nl80211_interface(payload: &[u8]) -> Option<String> {
+    attributes(payload.get(4..)?)
+        .into_iter()
+        .find(|(kind, _)| *kind == NL80211_ATTR_SSID)
+        .filter(|(_, ssid)| !ssid.is_empty())
+        .map(|(_, ssid)| String::from_utf8_lossy(ssid).into_owned())
+}
+
+/// Read a `sockaddr_in` or `sockaddr_in6`
+fn parse_sockaddr(value: &[u8]) -> Option<SocketAddr> {
+    let family = i32::from(u16::from_ne_bytes(value.get(..2)?.try_into().ok()?));
+    let port = u16::from_be_bytes(value.get(2..4)?.try_into().ok()?);
+    let address = match family {
+        libc::AF_INET => attribute_address(value.get(4..8)?)?,
+        libc::AF_INET6 => attribute_address(value.get(8..24)?)?,
+        _ => return None,
+    };
+    Some(SocketAddr::new(address, port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attribute_roundtrip() {
+        let mut buf = vec![];
+        push_attribute(&mut buf, FRA_PRIORITY, &32765u32.to_ne_bytes());
+        push_attribute(&mut buf, FRA_IIFNAME, b"lo\0");
+        push_attribute(&mut buf, FRA_DST, &[10, 0, 0, 0]);
+        assert_eq!(buf.len(), 8 + 8 + 8);
+
+        let attributes = attributes(&buf);
+        assert_eq!(attributes.len(), 3);
+        assert_eq!(attribute_u32(attributes[0].1), Some(32765));
+        assert_eq!(attribute_string(attributes[1].1), "lo");
+        assert_eq!(
+            attribute_address(attributes[2].1),
+            Some("10.0.0.0".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn parse_wg_quick_rule() {
+        // not from all fwmark 0xca6c lookup 51820
+        let mut payload = vec![libc::AF_INET as u8, 0, 0, 0, 0, 0, 0, FR_ACT_TO_TBL];
+        payload.extend_from_slice(&FIB_RULE_INVERT.to_ne_bytes());
+        push_attribute(&mut payload, FRA_PRIORITY, &32765u32.to_ne_bytes());
+        push_attribute(&mut payload, FRA_FWMARK, &0xca6cu32.to_ne_bytes());
+        push_attribute(&mut payload, FRA_FWMASK, &u32::MAX.to_ne_bytes());
+        push_attribute(&mut payload, FRA_TABLE, &51820u32.to_ne_bytes());
+
+        let rule = parse_rule(AddressFamily::Ipv4, &payload).unwrap();
+        assert_eq!(rule.priority, 32765);
+        assert_eq!(rule.action, RuleAction::Lookup(51820));
+        assert_eq!(rule.fwmark, Some(0xca6c));
+        assert!(rule.invert);
+        assert_eq!(rule.suppress_prefix_length, None);
+    }
+
+    #[test]
+    fn parse_suppress_prefix_rule() {
+        // from all lookup main suppress_prefixlength 0
+        let mut payload = vec![libc::AF_INET6 as u8, 0, 0, 0, 254, 0, 0, FR_ACT_TO_TBL];
+        payload.extend_from_slice(&0u32.to_ne_bytes());
+        push_attribute(&mut payload, FRA_PRIORITY, &32764u32.to_ne_bytes());
+        push_attribute(&mut payload, FRA_SUPPRESS_PREFIXLEN, &0u32.to_ne_bytes());
+
+        let rule = parse_rule(AddressFamily::Ipv6, &payload).unwrap();
+        assert_eq!(rule.family, AddressFamily::Ipv6);
+        assert_eq!(rule.action, RuleAction::Lookup(RT_TABLE_MAIN));
+        assert_eq!(rule.suppress_prefix_length, Some(0));
+        assert!(!rule.invert);
+    }
+
+    #[test]
+    fn parse_blackhole_route() {
+        // blackhole default table 51820 metric 1000
+        let mut payload = vec![libc::AF_INET as u8, 0, 0, 0, 0, 0, 0, RTN_BLACKHOLE];
+        payload.extend_from_slice(&0u32.to_ne_bytes());
+        push_attribute(&mut payload, RTA_TABLE, &51820u32.to_ne_bytes());
+        push_attribute(&mut payload, RTA_PRIORITY, &1000u32.to_ne_bytes());
+
+        let route = parse_route(AddressFamily::Ipv4, &payload).unwrap();
+        assert_eq!(route.kind, RouteKind::Blackhole);
+        assert_eq!(route.destination, "0.0.0.0/0".parse().unwrap());
+        assert_eq!(route.interface, "");
+        assert_eq!(route.table, 51820);
+        assert_eq!(route.metric, 1000);
+
+        // local routes are not read
+        payload[7] = 2;
+        assert_eq!(parse_route(AddressFamily::Ipv4, &payload), None);
+    }
+
+    #[test]
+    fn parse_road_warrior_policy() {
+        // src 10.10.0.5/32 dst 0.0.0.0/0 dir out priority 375423
+        //   tmpl src 192.168.1.10 dst 203.0.113.1 proto esp reqid 1 mode tunnel
+        let mut payload = vec![0u8; XFRM_POLICY_INFO_LEN];
+        payload[16..20].copy_from_slice(&[10, 10, 0, 5]);
+        payload[40..42].copy_from_slice(&(libc::AF_INET as u16).to_ne_bytes());
+        payload[42] = 0;
+        payload[43] = 32;
+        payload[152..156].copy_from_slice(&375423u32.to_ne_bytes());
+        payload[160] = 1;
+        let mut template = vec![0u8; XFRM_TEMPLATE_LEN];
+        template[0..4].copy_from_slice(&[203, 0, 113, 1]);
+        template[24..26].copy_from_slice(&(libc::AF_INET as u16).to_ne_bytes());
+        template[28..32].copy_from_slice(&[192, 168, 1, 10]);
+        template[44..48].copy_from_slice(&1u32.to_ne_bytes());
+        template[48] = XFRM_MODE_TUNNEL;
+        push_attribute(&mut payload, XFRMA_TMPL, &template);
+
+        let policy = parse_xfrm_policy(&payload).unwrap();
+        assert_eq!(policy.direction, PolicyDirection::Out);
+        assert_eq!(policy.source, "10.10.0.5/32".parse::<IpNet>().unwrap());
+        assert_eq!(policy.destination, "0.0.0.0/0".parse::<IpNet>().unwrap());
+        assert_eq!(policy.priority, 375423);
+        assert_eq!(policy.action, PolicyAction::Allow);
+        assert_eq!(
+            policy.templates,
+            vec![Template {
+                destination: "203.0.113.1".parse().unwrap(),
+                reqid: 1,
+                tunnel: true,
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_tunnel_state() {
+        let mut payload = vec![0u8; XFRM_SA_INFO_LEN];
+        payload[56..72].copy_from_slice(&"2001:db8::1".parse::<Ipv6Addr>().unwrap().octets());
+        payload[72..76].copy_from_slice(&0xc1a2b3c4u32.to_be_bytes());
+        payload[80..96].copy_from_slice(&"2001:db8::2".parse::<Ipv6Addr>().unwrap().octets());
+        payload[208..212].copy_from_slice(&7u32.to_ne_bytes());
+        payload[212..214].copy_from_slice(&(libc::AF_INET6 as u16).to_ne_bytes());
+        payload[214] = XFRM_MODE_TUNNEL;
+
+        let state = parse_xfrm_state(&payload).unwrap();
+        assert_eq!(state.destination, "2001:db8::1".parse::<IpAddr>().unwrap());
+        assert_eq!(state.source, "2001:db8::2".parse::<IpAddr>().unwrap());
+        assert_eq!(state.spi, 0xc1a2b3c4);
+        assert_eq!(state.reqid, 7);
+        assert!(state.tunnel);
+    }
+
+    #[test]
+    fn parse_wireguard_dump() {
+        let mut endpoint = vec![0u8; 16];
+        endpoint[..2].copy_from_slice(&(libc::AF_INET as u16).to_ne_bytes());
+        endpoint[2..4].copy_from_slice(&51820u16.to_be_bytes());
+        endpoint[4..8].copy_from_slice(&[198, 51, 100, 7]);
+        let mut handshake = 1_700_000_000u64.to_ne_bytes().to_vec();
+        handshake.extend_from_slice(&0u64.to_ne_bytes());
+
+        let mut peer = vec![];
+        push_attribute(&mut peer, WGPEER_A_PUBLIC_KEY, &[0; 32]);
+        push_attribute(&mut peer, WGPEER_A_ENDPOINT, &endpoint);
+        push_attribute(&mut peer, WGPEER_A_LAST_HANDSHAKE_TIME, &handshake);
+        push_attribute(&mut peer, WGPEER_A_RX_BYTES, &4096u64.to_ne_bytes());
+        push_attribute(&mut peer, WGPEER_A_TX_BYTES, &1024u64.to_ne_bytes());
+        let mut idle = vec![];
+        push_attribute(&mut idle, WGPEER_A_PUBLIC_KEY, &[1; 32]);
+        push_attribute(&mut idle, WGPEER_A_LAST_HANDSHAKE_TIME, &[0; 16]);
+        let mut peers = vec![];
+        push_attribute(&mut peers, 0, &peer);
+        push_attribute(&mut peers, 1, &idle);
+
+        let mut payload = vec![WG_CMD_GET_DEVICE, WG_GENL_VERSION, 0, 0];
+        push_attribute(&mut payload, WGDEVICE_A_IFNAME, b"wg0\0");
+        push_attribute(&mut payload, WGDEVICE_A_PEERS | 0x8000, &peers);
+
+        let peers = parse_wireguard_device(&payload);
+        assert_eq!(peers.len(), 2);
+        assert_eq!(
+            peers[0].endpoint,
+            Some("198.51.100.7:51820".parse().unwrap())
+        );
+        assert_eq!(peers[0].last_handshake, Some(1_700_000_000));
+        assert_eq!(peers[0].rx_bytes, 4096);
+        assert_eq!(peers[0].tx_bytes, 1024);
+        assert_eq!(peers[1].endpoint, None);
+        assert_eq!(peers[1].last_handshake, None);
+    }
+
+    #[test]
+    fn parse_nl80211_ssid() {
+        let mut payload = vec![NL80211_CMD_GET_INTERFACE, 1, 0, 0];
+        push_attribute(&mut payload, NL80211_ATTR_IFINDEX, &3u32.to_ne_bytes());
+        push_attribute(&mut payload, NL80211_ATTR_SSID, b"Home Office");
+        assert_eq!(
+            parse_nl80211_interface(&payload),
+            Some("Home Office".to_string())
+        );
+
+        // interfaces which are not associated have no SSID
+        let mut payload = vec![NL80211_CMD_GET_INTERFACE, 1, 0, 0];
+        push_attribute(&mut payload, NL80211_ATTR_IFINDEX, &3u32.to_ne_bytes());
+        assert_eq!(parse_nl80211_interface(&payload), None);
+    }
+
+    #[test]
+    fn read_host_state() {
+        // the host may not allow netlink sockets, only check that nothing panics
+        let _ = read_routes(AddressFamily::Ipv4);
+        let _ = read_rules(AddressFamily::Ipv6);
+        let _ = read_xfrm_policies();
+        let _ = read_xfrm_states();
+        let _ = read_wireguard_peers("wg0");
+        let _ = read_ssid("wlan0");
+    }
+}
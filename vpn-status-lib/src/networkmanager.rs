@@ -0,0 +1,357 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// State of a NetworkManager connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConnectionState {
+    Unknown,
+    /// the connection is negotiating, e.g. waiting for authentication
+    Activating,
+    Activated,
+    Deactivating,
+    Deactivated,
+    /// the VPN plugin failed to establish the connection
+    Failed,
+}
+
+impl ConnectionState {
+    /// Get the state from `NMActiveConnectionState`
+    pub fn from_active(state: u32) -> Self {
+        match state {
+            1 => Self::Activating,
+            2 => Self::Activated,
+            3 => Self::Deactivating,
+            4 => Self::Deactivated,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Get the state from `NMVpnConnectionState`, which tells apart failed connections
+    pub fn from_vpn(state: u32) -> Self {
+        match state {
+            1..=4 => Self::Activating,
+            5 => Self::Activated,
+            6 => Self::Failed,
+            7 => Self::Deactivated,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+impl Display for ConnectionState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::Unknown => write!(f, "unknown"),
+            Self::Activating => write!(f, "activating"),
+            Self::Activated => write!(f, "activated"),
+            Self::Deactivating => write!(f, "deactivating"),
+            Self::Deactivated => write!(f, "deactivated"),
+            Self::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+/// Active VPN connection managed by NetworkManager
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Connection {
+    /// name of the connection profile
+    pub id: String,
+    pub uuid: String,
+    /// type of the connection profile, e.g. `vpn` or `wireguard`
+    pub kind: String,
+    pub state: ConnectionState,
+    /// tunnel interface, unknown for connections run by VPN plugins
+    pub interface: Option<String>,
+}
+
+#[cfg(feature = "networkmanager")]
+pub use dbus::{active_connections, active_connections_with};
+
+#[cfg(feature = "networkmanager")]
+mod dbus {
+    use super::{Connection, ConnectionState};
+    use zbus::blocking::proxy::Builder;
+    use zbus::blocking::{self, Proxy};
+    use zbus::proxy::CacheProperties;
+    use zbus::zvariant::OwnedObjectPath;
+
+    const DESTINATION: &str = "org.freedesktop.NetworkManager";
+    const PATH: &str = "/org/freedesktop/NetworkManager";
+
+    /// Connection profile types which are VPNs without being run by a VPN plugin
+    const TUNNEL_TYPES: &[&str] = &["wireguard", "tun"];
+
+    fn proxy<'a>(
+        connection: &blocking::Connection,
+        path: &'a str,
+        interface: &'a str,
+    ) -> zbus::Result<Proxy<'a>> {
+        Builder::new(connection)
+            .destination(DESTINATION)?
+            .path(path)?
+            .interface(interface)?
+            .cache_properties(CacheProperties::No)
+            .build()
+    }
+
+    /// Get the active VPN connections from NetworkManager on the system bus.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// for connection in vpn_status_lib::networkmanager::active_connections().unwrap() {
+    ///     println!("{} is {}", connection.id, connection.state);
+    /// }
+    /// ```
+    pub fn active_connections() -> zbus::Result<Vec<Connection>> {
+        active_connections_with(&blocking::Connection::system()?)
+    }
+
+    /// Get the active VPN connections from NetworkManager on the given bus connection
+    pub fn active_connections_with(
+        connection: &blocking::Connection,
+    ) -> zbus::Result<Vec<Connection>> {
+        let manager = proxy(connection, PATH, DESTINATION)?;
+        let paths: Vec<OwnedObjectPath> = manager.get_property("ActiveConnections")?;
+
+        let mut connections = vec![];
+        for path in paths {
+            let active = proxy(
+                connection,
+                path.as_str(),
+                "org.freedesktop.NetworkManager.Connection.Active",
+            )?;
+            let kind: String = active.get_property("Type")?;
+            let vpn: bool = active.get_property("Vpn")?;
+            if !vpn && !TUNNEL_TYPES.contains(&kind.as_str()) {
+                continue;
+            }
+
+            let state = if vpn {
+                let plugin = proxy(
+                    connection,
+                    path.as_str(),
+                    "org.freedesktop.NetworkManager.VPN.Connection",
+                )?;
+                ConnectionState::from_vpn(plugin.get_property("VpnState")?)
+            } else {
+                ConnectionState::from_active(active.get_property("State")?)
+            };
+
+            // plugin connections list the device carrying the encrypted traffic, not the tunnel
+            let interface = if vpn {
+                None
+            } else {
+                let devices: Vec<OwnedObjectPath> = active.get_property("Devices")?;
+                match devices.first() {
+                    Some(device) => {
+                        let device = proxy(
+                            connection,
+                            device.as_str(),
+                            "org.freedesktop.NetworkManager.Device",
+                        )?;
+                        let interface: String = device.get_property("IpInterface")?;
+                        Some(interface).filter(|interface| !interface.is_empty())
+                    }
+                    None => None,
+                }
+            };
+
+            connections.push(Connection {
+                id: active.get_property("Id")?,
+                uuid: active.get_property("Uuid")?,
+                kind,
+                state,
+                interface,
+            });
+        }
+        Ok(connections)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::os::unix::net::UnixStream;
+        use zbus::interface;
+
+        struct Manager {
+            active: Vec<OwnedObjectPath>,
+        }
+
+        #[interface(name = "org.freedesktop.NetworkManager")]
+        impl Manager {
+            #[zbus(property)]
+            fn active_connections(&self) -> Vec<OwnedObjectPath> {
+                self.active.clone()
+            }
+        }
+
+        struct Active {
+            id: &'static str,
+            kind: &'static str,
+            state: u32,
+            vpn: bool,
+            devices: Vec<OwnedObjectPath>,
+        }
+
+        #[interface(name = "org.freedesktop.NetworkManager.Connection.Active")]
+        impl Active {
+            #[zbus(property)]
+            fn id(&self) -> String {
+                self.id.to_string()
+            }
+            #[zbus(property)]
+            fn uuid(&self) -> String {
+                format!("uuid-{}", self.id)
+            }
+            #[zbus(property, name = "Type")]
+            fn kind(&self) -> String {
+                self.kind.to_string()
+            }
+            #[zbus(property)]
+            fn state(&self) -> u32 {
+                self.state
+            }
+            #[zbus(property)]
+            fn vpn(&self) -> bool {
+                self.vpn
+            }
+            #[zbus(property)]
+            fn devices(&self) -> Vec<OwnedObjectPath> {
+                self.devices.clone()
+            }
+        }
+
+        struct Plugin {
+            state: u32,
+        }
+
+        #[interface(name = "org.freedesktop.NetworkManager.VPN.Connection")]
+        impl Plugin {
+            #[zbus(property)]
+            fn vpn_state(&self) -> u32 {
+                self.state
+            }
+        }
+
+        struct Device {
+            interface: &'static str,
+        }
+
+        #[interface(name = "org.freedesktop.NetworkManager.Device")]
+        impl Device {
+            #[zbus(property)]
+            fn ip_interface(&self) -> String {
+                self.interface.to_string()
+            }
+        }
+
+        fn path(path: &str) -> OwnedObjectPath {
+            OwnedObjectPath::try_from(path).unwrap()
+        }
+
+        /// Serve a NetworkManager stand-in on one end of a socket pair, and connect to it
+        fn mock() -> (blocking::Connection, blocking::Connection) {
+            let (server, client) = UnixStream::pair().unwrap();
+            let active = |id| format!("/org/freedesktop/NetworkManager/ActiveConnection/{}", id);
+            let device = |id| format!("/org/freedesktop/NetworkManager/Devices/{}", id);
+            let server = std::thread::spawn(move || {
+                blocking::connection::Builder::async_io_unix_stream(server)
+                    .server(zbus::Guid::generate())
+                    .unwrap()
+                    .p2p()
+                    .serve_at(
+                        PATH,
+                        Manager {
+                            active: vec![path(&active(1)), path(&active(2)), path(&active(3))],
+                        },
+                    )
+                    .unwrap()
+                    .serve_at(
+                        active(1),
+                        Active {
+                            id: "Wired connection 1",
+                            kind: "802-3-ethernet",
+                            state: 2,
+                            vpn: false,
+                            devices: vec![path(&device(2))],
+                        },
+                    )
+                    .unwrap()
+                    .serve_at(
+                        active(2),
+                        Active {
+                            id: "Office",
+                            kind: "vpn",
+                            state: 1,
+                            vpn: true,
+                            devices: vec![path(&device(2))],
+                        },
+                    )
+                    .unwrap()
+                    .serve_at(active(2), Plugin { state: 3 })
+                    .unwrap()
+                    .serve_at(
+                        active(3),
+                        Active {
+                            id: "Home",
+                            kind: "wireguard",
+                            state: 2,
+                            vpn: false,
+                            devices: vec![path(&device(5))],
+                        },
+                    )
+                    .unwrap()
+                    .serve_at(device(2), Device { interface: "eth0" })
+                    .unwrap()
+                    .serve_at(device(5), Device { interface: "wg0" })
+                    .unwrap()
+                    .build()
+                    .unwrap()
+            });
+            let client = blocking::connection::Builder::async_io_unix_stream(client)
+                .p2p()
+                .build()
+                .unwrap();
+            (server.join().unwrap(), client)
+        }
+
+        #[test]
+        fn vpn_connections() {
+            let (_server, client) = mock();
+            let connections = active_connections_with(&client).unwrap();
+            assert_eq!(
+                connections,
+                vec![
+                    Connection {
+                        id: "Office".to_string(),
+                        uuid: "uuid-Office".to_string(),
+                        kind: "vpn".to_string(),
+                        state: ConnectionState::Activating,
+                        interface: None,
+                    },
+                    Connection {
+                        id: "Home".to_string(),
+                        uuid: "uuid-Home".to_string(),
+                        kind: "wireguard".to_string(),
+                        state: ConnectionState::Activated,
+                        interface: Some("wg0".to_string()),
+                    },
+                ]
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn connection_states() {
+        assert_eq!(ConnectionState::from_active(1), ConnectionState::Activating);
+        assert_eq!(ConnectionState::from_active(2), ConnectionState::Activated);
+        assert_eq!(ConnectionState::from_vpn(2), ConnectionState::Activating);
+        assert_eq!(ConnectionState::from_vpn(6), ConnectionState::Failed);
+        assert_eq!(ConnectionState::from_vpn(9), ConnectionState::Unknown);
+    }
+}
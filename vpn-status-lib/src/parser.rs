@@ -8,6 +8,19 @@ pub enum Syntax {
     Ip,
     City,
     Country,
+    Leak,
+    Dns,
+    DnsLeak,
+    Provider,
+    Rx,
+    Tx,
+    Handshake,
+    Endpoint,
+    Uptime,
+    Violations,
+    Client,
+    Connection,
+    Chain,
     String(String),
 }
 
@@ -19,6 +32,19 @@ impl FromStr for Syntax {
             "ip" => Ok(Self::Ip),
             "city" => Ok(Self::City),
             "country" => Ok(Self::Country),
+            "leak" => Ok(Self::Leak),
+            "dns" => Ok(Self::Dns),
+            "dns_leak" => Ok(Self::DnsLeak),
+            "provider" => Ok(Self::Provider),
+            "rx" => Ok(Self::Rx),
+            "tx" => Ok(Self::Tx),
+            "handshake" => Ok(Self::Handshake),
+            "endpoint" => Ok(Self::Endpoint),
+            "uptime" => Ok(Self::Uptime),
+            "violations" => Ok(Self::Violations),
+            "client" => Ok(Self::Client),
+            "connection" => Ok(Self::Connection),
+            "chain" => Ok(Self::Chain),
             _ => Ok(Self::String(str.to_string())),
         }
     }
@@ -32,6 +58,24 @@ pub struct Lookup {
     pub country: String,
 }
 
+/// Detection details struct
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Details {
+    pub leak: String,
+    pub dns: String,
+    pub dns_leak: String,
+    pub provider: String,
+    pub rx: String,
+    pub tx: String,
+    pub handshake: String,
+    pub endpoint: String,
+    pub uptime: String,
+    pub violations: String,
+    pub client: String,
+    pub connection: String,
+    pub chain: String,
+}
+
 /// Parse output_format into syntax tokens
 pub fn parse(format: &str) -> Vec<Syntax> {
     let tokens: Vec<&str> = format.split_terminator(&['{', '}']).collect();
@@ -48,6 +92,16 @@ pub fn parse(format: &str) -> Vec<Syntax> {
 
 /// Constructs an output string with given format
 pub fn make_output(input: Vec<Syntax>, status: &str, lookup: Option<Lookup>) -> String {
+    make_output_with_details(input, status, lookup, Details::default())
+}
+
+/// Constructs an output string with given format, filling in the detection details
+pub fn make_output_with_details(
+    input: Vec<Syntax>,
+    status: &str,
+    lookup: Option<Lookup>,
+    details: Details,
+) -> String {
     let mut output = String::new();
     let lookup = lookup.unwrap_or_default();
 
@@ -57,6 +111,19 @@ pub fn make_output(input: Vec<Syntax>, status: &str, lookup: Option<Lookup>) ->
             Syntax::Ip => output = format!("{}{}", output, lookup.ip),
             Syntax::City => output = format!("{}{}", output, lookup.city),
             Syntax::Country => output = format!("{}{}", output, lookup.country),
+            Syntax::Leak => output = format!("{}{}", output, details.leak),
+            Syntax::Dns => output = format!("{}{}", output, details.dns),
+            Syntax::DnsLeak => output = format!("{}{}", output, details.dns_leak),
+            Syntax::Provider => output = format!("{}{}", output, details.provider),
+            Syntax::Rx => output = format!("{}{}", output, details.rx),
+            Syntax::Tx => output = format!("{}{}", output, details.tx),
+            Syntax::Handshake => output = format!("{}{}", output, details.handshake),
+            Syntax::Endpoint => output = format!("{}{}", output, details.endpoint),
+            Syntax::Uptime => output = format!("{}{}", output, details.uptime),
+            Syntax::Violations => output = format!("{}{}", output, details.violations),
+            Syntax::Client => output = format!("{}{}", output, details.client),
+            Syntax::Connection => output = format!("{}{}", output, details.connection),
+            Syntax::Chain => output = format!("{}{}", output, details.chain),
             Syntax::String(s) => output = format!("{}{}", output, s),
         }
     }
@@ -70,6 +137,19 @@ pub fn make_output_styled(
     lookup: Option<Lookup>,
     style: Vec<String>,
     color: &str,
+) -> String {
+    make_output_styled_with_details(input, status, lookup, Details::default(), style, color)
+}
+
+/// Constructs an output string with given format and detection details, where the static
+/// strings are styled
+pub fn make_output_styled_with_details(
+    input: Vec<Syntax>,
+    status: &str,
+    lookup: Option<Lookup>,
+    details: Details,
+    style: Vec<String>,
+    color: &str,
 ) -> String {
     let mut output = String::new();
     let lookup = lookup.unwrap_or_default();
@@ -80,6 +160,19 @@ pub fn make_output_styled(
             Syntax::Ip => output = format!("{}{}", output, lookup.ip),
             Syntax::City => output = format!("{}{}", output, lookup.city),
             Syntax::Country => output = format!("{}{}", output, lookup.country),
+            Syntax::Leak => output = format!("{}{}", output, details.leak),
+            Syntax::Dns => output = format!("{}{}", output, details.dns),
+            Syntax::DnsLeak => output = format!("{}{}", output, details.dns_leak),
+            Syntax::Provider => output = format!("{}{}", output, details.provider),
+            Syntax::Rx => output = format!("{}{}", output, details.rx),
+            Syntax::Tx => output = format!("{}{}", output, details.tx),
+            Syntax::Handshake => output = format!("{}{}", output, details.handshake),
+            Syntax::Endpoint => output = format!("{}{}", output, details.endpoint),
+            Syntax::Uptime => output = format!("{}{}", output, details.uptime),
+            Syntax::Violations => output = format!("{}{}", output, details.violations),
+            Syntax::Client => output = format!("{}{}", output, details.client),
+            Syntax::Connection => output = format!("{}{}", output, details.connection),
+            Syntax::Chain => output = format!("{}{}", output, details.chain),
             Syntax::String(s) => {
                 output = format!("{}{}", output, styles::apply_style(s, style.clone(), color))
             }
@@ -139,6 +232,46 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_status_with_leak() {
+        let format = "{status} {leak}";
+        let expected_tokens = vec![
+            Syntax::Status,
+            Syntax::String(" ".to_string()),
+            Syntax::Leak,
+        ];
+        let tokens = parse(format);
+        assert_eq!(tokens, expected_tokens);
+
+        let details = Details {
+            leak: "ipv6 leak".to_string(),
+            ..Default::default()
+        };
+        let out = make_output_with_details(tokens, "enabled", None, details);
+        assert_eq!(out, "enabled ipv6 leak");
+    }
+
+    #[test]
+    fn parse_dns() {
+        let format = "{status} via {dns}{dns_leak}";
+        let expected_tokens = vec![
+            Syntax::Status,
+            Syntax::String(" via ".to_string()),
+            Syntax::Dns,
+            Syntax::DnsLeak,
+        ];
+        let tokens = parse(format);
+        assert_eq!(tokens, expected_tokens);
+
+        let details = Details {
+            dns: "10.2.0.1, 192.168.1.1".to_string(),
+            dns_leak: " (dns leak)".to_string(),
+            ..Default::default()
+        };
+        let out = make_output_with_details(tokens, "enabled", None, details);
+        assert_eq!(out, "enabled via 10.2.0.1, 192.168.1.1 (dns leak)");
+    }
+
     #[test]
     fn parse_status_only() {
         let format = "{status}";
@@ -0,0 +1,150 @@
+use crate::routes::Route;
+use crate::rules::Rule;
+use log::debug;
+use netdev::ipnet::IpNet;
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, ToSocketAddrs};
+
+/// Where the traffic to a destination is required to go
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Requirement {
+    /// through one of the tunnels, e.g. internal ranges
+    Tunnel,
+    /// outside of the tunnels, e.g. local printers
+    Bypass,
+}
+
+/// Destination egressing against its requirement
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Violation {
+    /// destination as configured
+    pub destination: String,
+    /// address of the destination routed against the requirement
+    pub address: IpAddr,
+    pub requirement: Requirement,
+    /// egress interface, none if the destination is unreachable
+    pub interface: Option<String>,
+}
+
+/// Get the addresses to check for a configured destination.
+///
+/// Networks are sampled at their boundaries and at the boundaries of the routes and rules
+/// inside of them, so that each part of the network routed differently is checked.
+/// Host names are resolved.
+pub fn addresses(destination: &str, routes: &[Route], rules: &[Rule]) -> Vec<IpAddr> {
+    let destination = destination.trim();
+    if let Ok(address) = destination.parse::<IpAddr>() {
+        return vec![address];
+    }
+    if let Ok(net) = destination.parse::<IpNet>() {
+        return network_samples(&net, routes, rules);
+    }
+    match (destination, 0).to_socket_addrs() {
+        Ok(addresses) => {
+            let mut addresses: Vec<IpAddr> = addresses.map(|address| address.ip()).collect();
+            addresses.dedup();
+            addresses
+        }
+        Err(error) => {
+            debug!("failed resolving {}: {}", destination, error);
+            vec![]
+        }
+    }
+}
+
+fn network_samples(net: &IpNet, routes: &[Route], rules: &[Rule]) -> Vec<IpAddr> {
+    let net = net.trunc();
+    let prefixes = std::iter::once(net)
+        .chain(routes.iter().map(|route| route.destination))
+        .chain(rules.iter().filter_map(|rule| rule.destination))
+        .filter(|prefix| net.contains(prefix));
+
+    let mut samples = vec![];
+    for prefix in prefixes {
+        let candidates = [
+            Some(prefix.network()),
+            Some(prefix.broadcast()),
+            step(prefix.network(), false),
+            step(prefix.broadcast(), true),
+        ];
+        for address in candidates.into_iter().flatten() {
+            if net.contains(&address) && !samples.contains(&address) {
+                samples.push(address);
+            }
+        }
+    }
+    samples
+}
+
+/// Get the next or previous address
+fn step(address: IpAddr, forward: bool) -> Option<IpAddr> {
+    match address {
+        IpAddr::V4(address) => {
+            let value = u32::from(address);
+            let value = if forward {
+                value.checked_add(1)
+            } else {
+                value.checked_sub(1)
+            };
+            value.map(|value| IpAddr::V4(value.into()))
+        }
+        IpAddr::V6(address) => {
+            let value = u128::from(address);
+            let value = if forward {
+                value.checked_add(1)
+            } else {
+                value.checked_sub(1)
+            };
+            value.map(|value| IpAddr::V6(value.into()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn address(address: &str) -> IpAddr {
+        address.parse().unwrap()
+    }
+
+    #[test]
+    fn single_address() {
+        assert_eq!(addresses("10.20.0.5", &[], &[]), vec![address("10.20.0.5")]);
+        assert_eq!(addresses(" ::1 ", &[], &[]), vec![address("::1")]);
+    }
+
+    #[test]
+    fn network_boundaries() {
+        assert_eq!(
+            addresses("10.0.0.0/8", &[], &[]),
+            vec![address("10.0.0.0"), address("10.255.255.255")]
+        );
+
+        // a more specific route splits the network in three parts
+        let routes = vec![
+            Route::new("10.20.0.0/16".parse().unwrap(), "tun0"),
+            Route::new("192.168.1.0/24".parse().unwrap(), "eth0"),
+        ];
+        let samples = addresses("10.0.0.0/8", &routes, &[]);
+        assert_eq!(
+            samples,
+            vec![
+                address("10.0.0.0"),
+                address("10.255.255.255"),
+                address("10.20.0.0"),
+                address("10.20.255.255"),
+                address("10.19.255.255"),
+                address("10.21.0.0"),
+            ]
+        );
+    }
+
+    #[test]
+    fn host_name() {
+        assert!(addresses("localhost", &[], &[])
+            .iter()
+            .all(|address| address.is_loopback()));
+        assert!(addresses("invalid.invalid", &[], &[]).is_empty());
+    }
+}
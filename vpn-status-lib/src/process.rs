@@ -0,0 +1,221 @@
+use crate::kind::TunnelKind;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Process names of VPN clients, the kind of VPN they run, and whether they keep running
+/// while the VPN is disconnected
+const CLIENTS: &[(&str, TunnelKind, bool)] = &[
+    ("openvpn", TunnelKind::OpenVpn, false),
+    ("wireguard-go", TunnelKind::WireGuard, false),
+    ("boringtun", TunnelKind::WireGuard, false),
+    ("boringtun-cli", TunnelKind::WireGuard, false),
+    ("mullvad-daemon", TunnelKind::WireGuard, true),
+    ("openconnect", TunnelKind::AnyConnect, false),
+    ("vpnagentd", TunnelKind::AnyConnect, true),
+    ("PanGPS", TunnelKind::GlobalProtect, true),
+    ("gpclient", TunnelKind::GlobalProtect, false),
+    ("charon", TunnelKind::Ipsec, true),
+    ("pluto", TunnelKind::Ipsec, true),
+    ("pppd", TunnelKind::Ppp, false),
+    ("tailscaled", TunnelKind::Tailscale, true),
+    ("zerotier-one", TunnelKind::ZeroTier, true),
+    ("nebula", TunnelKind::Nebula, false),
+];
+
+/// Running VPN client process
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Client {
+    pub pid: u32,
+    /// process name
+    pub name: String,
+    pub kind: TunnelKind,
+    /// whether the client keeps running while the VPN is disconnected
+    pub daemon: bool,
+    /// tunnel interface named on the command line, or used by default
+    pub interface: Option<String>,
+}
+
+/// Find the running VPN clients in the proc file system mounted at the given root.
+///
+/// # Example
+/// ```rust
+/// # use std::path::Path;
+/// for client in vpn_status_lib::process::scan(Path::new("/proc")) {
+///     println!("{} ({}) is running", client.name, client.pid);
+/// }
+/// ```
+pub fn scan(root: &Path) -> Vec<Client> {
+    let entries = match std::fs::read_dir(root) {
+        Ok(entries) => entries,
+        Err(error) => {
+            debug!("failed reading {}: {}", root.display(), error);
+            return vec![];
+        }
+    };
+    let mut clients: Vec<Client> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let pid = entry.file_name().to_str()?.parse().ok()?;
+            read_client(&entry.path(), pid)
+        })
+        .collect();
+    clients.sort_by_key(|client| client.pid);
+    clients
+}
+
+fn read_client(path: &Path, pid: u32) -> Option<Client> {
+    // processes might exit while scanning
+    let comm = std::fs::read_to_string(path.join("comm")).ok()?;
+    let cmdline = std::fs::read(path.join("cmdline")).unwrap_or_default();
+    let args: Vec<String> = cmdline
+        .split(|byte| *byte == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect();
+
+    // the name in comm is truncated to 15 bytes, the executable path is not
+    let executable = args
+        .first()
+        .and_then(|arg| Path::new(arg).file_name())
+        .map(|name| name.to_string_lossy().into_owned());
+    let (name, kind, daemon) = [Some(comm.trim().to_string()), executable]
+        .into_iter()
+        .flatten()
+        .find_map(|name| {
+            let (_, kind, daemon) = CLIENTS.iter().find(|(client, _, _)| *client == name)?;
+            Some((name, kind.clone(), *daemon))
+        })?;
+    let interface = interface(&name, &args);
+    Some(Client {
+        pid,
+        name,
+        kind,
+        daemon,
+        interface,
+    })
+}
+
+/// Get the tunnel interface of a client from its arguments
+fn interface(name: &str, args: &[String]) -> Option<String> {
+    let option = |names: &[&str]| {
+        args.iter().enumerate().find_map(|(i, arg)| {
+            names.iter().find_map(|option| {
+                if arg == option {
+                    args.get(i + 1).cloned()
+                } else {
+                    arg.strip_prefix(&format!("{}=", option))
+                        .map(str::to_string)
+                }
+            })
+        })
+    };
+    match name {
+        // a bare device type lets the kernel pick the next free interface
+        "openvpn" => option(&["--dev", "dev"]).filter(|dev| dev != "tun" && dev != "tap"),
+        "openconnect" => option(&["--interface", "-i"]),
+        // userspace networking runs without an interface
+        "tailscaled" => match option(&["--tun"]) {
+            Some(tun) if tun == "userspace-networking" => None,
+            tun => tun.or(Some("tailscale0".to_string())),
+        },
+        // the interface is the only positional argument
+        "wireguard-go" | "boringtun" | "boringtun-cli" => args
+            .iter()
+            .skip(1)
+            .find(|arg| !arg.starts_with('-'))
+            .cloned(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scan_fixture() {
+        let clients = scan(Path::new("fixtures/proc"));
+        assert_eq!(
+            clients,
+            vec![
+                Client {
+                    pid: 812,
+                    name: "openvpn".to_string(),
+                    kind: TunnelKind::OpenVpn,
+                    daemon: false,
+                    interface: Some("tun1".to_string()),
+                },
+                Client {
+                    pid: 1024,
+                    name: "wireguard-go".to_string(),
+                    kind: TunnelKind::WireGuard,
+                    daemon: false,
+                    interface: Some("wg0".to_string()),
+                },
+                Client {
+                    pid: 1377,
+                    name: "mullvad-daemon".to_string(),
+                    kind: TunnelKind::WireGuard,
+                    daemon: true,
+                    interface: None,
+                },
+                Client {
+                    pid: 2048,
+                    name: "tailscaled".to_string(),
+                    kind: TunnelKind::Tailscale,
+                    daemon: true,
+                    interface: Some("tailscale0".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_root() {
+        assert!(scan(Path::new("fixtures/missing")).is_empty());
+    }
+
+    #[test]
+    fn interface_arguments() {
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            interface(
+                "openvpn",
+                &args(&["openvpn", "--config", "a.ovpn", "--dev", "tun3"])
+            ),
+            Some("tun3".to_string())
+        );
+        assert_eq!(
+            interface(
+                "openconnect",
+                &args(&["openconnect", "--interface=vpn0", "host"])
+            ),
+            Some("vpn0".to_string())
+        );
+        assert_eq!(
+            interface(
+                "tailscaled",
+                &args(&["tailscaled", "--tun=userspace-networking"])
+            ),
+            None
+        );
+        assert_eq!(
+            interface("tailscaled", &args(&["tailscaled", "--tun", "ts0"])),
+            Some("ts0".to_string())
+        );
+        assert_eq!(
+            interface("tailscaled", &args(&["tailscaled"])),
+            Some("tailscale0".to_string())
+        );
+        assert_eq!(interface("openvpn", &args(&["openvpn", "a.ovpn"])), None);
+        assert_eq!(
+            interface("openvpn", &args(&["openvpn", "--dev", "tun"])),
+            None
+        );
+        assert_eq!(
+            interface("openvpn", &args(&["openvpn", "--dev", "tap"])),
+            None
+        );
+    }
+}
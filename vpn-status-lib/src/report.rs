@@ -0,0 +1,416 @@
+use crate::kind::TunnelKind;
+use crate::routes::Route;
+use crate::{Detection, VpnStatus};
+use netdev::ipnet::IpNet;
+use std::fmt::Display;
+use std::net::IpAddr;
+
+/// Source of evidence about the VPN
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    /// interface carrying the IPv4 default route
+    DefaultRoute,
+    /// routes and IPsec policies sending traffic into the tunnels
+    RoutingRules,
+    /// kind of VPN behind the tunnel interfaces
+    TunnelKind,
+    /// traffic counters of the tunnels
+    Traffic,
+    /// public IP address within the known VPN exit ranges
+    PublicIp,
+    /// running VPN client processes
+    Process,
+}
+
+impl Signal {
+    /// Weight of the signal in the confidence score
+    pub fn weight(&self) -> u32 {
+        match self {
+            Self::DefaultRoute => 3,
+            Self::RoutingRules => 2,
+            Self::TunnelKind => 1,
+            Self::Traffic => 1,
+            Self::PublicIp => 3,
+            Self::Process => 1,
+        }
+    }
+}
+
+impl Display for Signal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::DefaultRoute => write!(f, "default route"),
+            Self::RoutingRules => write!(f, "routing rules"),
+            Self::TunnelKind => write!(f, "tunnel kind"),
+            Self::Traffic => write!(f, "traffic"),
+            Self::PublicIp => write!(f, "public ip"),
+            Self::Process => write!(f, "process"),
+        }
+    }
+}
+
+/// What a signal tells about the traffic
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// the signal points to traffic going through the VPN
+    Tunneled,
+    /// the signal points to traffic bypassing the VPN
+    Direct,
+    /// the signal is not available or ambiguous
+    Inconclusive,
+}
+
+impl Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::Tunneled => write!(f, "tunneled"),
+            Self::Direct => write!(f, "direct"),
+            Self::Inconclusive => write!(f, "inconclusive"),
+        }
+    }
+}
+
+/// Verdict of a single signal
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignalReport {
+    pub signal: Signal,
+    pub verdict: Verdict,
+    /// observation behind the verdict
+    pub detail: String,
+    /// whether the verdict agrees with the reported status, none if it is inconclusive
+    pub agrees: Option<bool>,
+}
+
+/// Status of the VPN with the signals behind it
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatusReport {
+    pub status: VpnStatus,
+    /// weighted share of the conclusive signals agreeing with the status, from 0 to 1
+    pub confidence: f64,
+    pub signals: Vec<SignalReport>,
+    pub detection: Detection,
+}
+
+impl StatusReport {
+    /// Combine the signals of a detection into a report.
+    ///
+    /// The public IP address is only judged when exit ranges are known.
+    pub fn new(
+        detection: Detection,
+        routes: &[Route],
+        public_ip: Option<IpAddr>,
+        exit_ranges: &[IpNet],
+    ) -> Self {
+        let signals = vec![
+            default_route(&detection),
+            routing_rules(&detection, routes),
+            tunnel_kind(&detection),
+            traffic(&detection),
+            self::public_ip(public_ip, exit_ranges),
+            process(&detection),
+        ];
+        let tunneled_default = signals[0].1 == Verdict::Tunneled;
+        let signals: Vec<SignalReport> = signals
+            .into_iter()
+            .map(|(signal, verdict, detail)| {
+                let agrees = expected(detection.status, signal, tunneled_default)
+                    .filter(|_| verdict != Verdict::Inconclusive)
+                    .map(|expected| expected == verdict);
+                SignalReport {
+                    signal,
+                    verdict,
+                    detail,
+                    agrees,
+                }
+            })
+            .collect();
+
+        let weight = |agreeing: bool| -> u32 {
+            signals
+                .iter()
+                .filter(|report| {
+                    report.agrees.is_some() && (!agreeing || report.agrees == Some(true))
+                })
+                .map(|report| report.signal.weight())
+                .sum()
+        };
+        let confidence = match weight(false) {
+            0 => 1.0,
+            total => weight(true) as f64 / total as f64,
+        };
+
+        Self {
+            status: detection.status,
+            confidence,
+            signals,
+            detection,
+        }
+    }
+}
+
+/// Get the verdict a signal should have for the status
+fn expected(status: VpnStatus, signal: Signal, tunneled_default: bool) -> Option<Verdict> {
+    use Verdict::*;
+    let status = match status {
+        // destinations are routed against the policy on top of the actual status
+        VpnStatus::Violation if tunneled_default => VpnStatus::Enabled,
+        VpnStatus::Violation => VpnStatus::SplitTunnel,
+        // the traffic is tunneled, only through too few tunnels
+        VpnStatus::ShortChain => VpnStatus::Enabled,
+        status => status,
+    };
+    match (status, signal) {
+        (VpnStatus::Offline | VpnStatus::NoGateway, _) => None,
+        (VpnStatus::Enabled, _) => Some(Tunneled),
+        (VpnStatus::Stalled, Signal::Traffic) => Some(Direct),
+        (VpnStatus::Stalled, Signal::PublicIp) => None,
+        (VpnStatus::Stalled, _) => Some(Tunneled),
+        (VpnStatus::SplitTunnel, Signal::DefaultRoute | Signal::PublicIp) => Some(Direct),
+        (VpnStatus::SplitTunnel, _) => Some(Tunneled),
+        (VpnStatus::Connecting, Signal::Process) => Some(Tunneled),
+        _ => Some(Direct),
+    }
+}
+
+fn default_route(detection: &Detection) -> (Signal, Verdict, String) {
+    let signal = Signal::DefaultRoute;
+    match detection.route {
+        Some(ref found) => {
+            let interface = &found.route.interface;
+            if detection
+                .tunnels
+                .iter()
+                .any(|tunnel| &tunnel.name == interface)
+            {
+                (
+                    signal,
+                    Verdict::Tunneled,
+                    format!("via tunnel {}", interface),
+                )
+            } else {
+                (signal, Verdict::Direct, format!("via {}", interface))
+            }
+        }
+        None => (
+            signal,
+            Verdict::Inconclusive,
+            "no default route".to_string(),
+        ),
+    }
+}
+
+fn routing_rules(detection: &Detection, routes: &[Route]) -> (Signal, Verdict, String) {
+    let signal = Signal::RoutingRules;
+    let count = routes
+        .iter()
+        .filter(|route| {
+            detection
+                .tunnels
+                .iter()
+                .any(|tunnel| !tunnel.mesh && tunnel.name == route.interface)
+        })
+        .count();
+    let policies = detection.ipsec.len();
+    if count > 0 || policies > 0 {
+        let detail = format!("{} tunnel routes, {} ipsec policies", count, policies);
+        (signal, Verdict::Tunneled, detail)
+    } else {
+        let detail = "no routes into tunnels".to_string();
+        (signal, Verdict::Direct, detail)
+    }
+}
+
+fn tunnel_kind(detection: &Detection) -> (Signal, Verdict, String) {
+    let signal = Signal::TunnelKind;
+    let tunnels: Vec<_> = detection
+        .tunnels
+        .iter()
+        .filter(|tunnel| !tunnel.mesh)
+        .collect();
+    let known: Vec<String> = tunnels
+        .iter()
+        .filter(|tunnel| tunnel.kind != TunnelKind::Unknown)
+        .map(|tunnel| format!("{} ({})", tunnel.name, tunnel.kind))
+        .collect();
+    if !known.is_empty() {
+        (signal, Verdict::Tunneled, known.join(", "))
+    } else if !tunnels.is_empty() {
+        let detail = "tunnels of unknown kind".to_string();
+        (signal, Verdict::Inconclusive, detail)
+    } else {
+        (signal, Verdict::Direct, "no tunnels".to_string())
+    }
+}
+
+fn traffic(detection: &Detection) -> (Signal, Verdict, String) {
+    let signal = Signal::Traffic;
+    let samples: Vec<_> = detection
+        .tunnels
+        .iter()
+        .filter(|tunnel| !tunnel.mesh)
+        .filter_map(|tunnel| Some((&tunnel.name, detection.traffic.get(&tunnel.name)?)))
+        .collect();
+    if let Some((name, _)) = samples
+        .iter()
+        .find(|(_, traffic)| traffic.rx_rate.is_some_and(|rate| rate > 0.0))
+    {
+        (signal, Verdict::Tunneled, format!("{} receiving", name))
+    } else if let Some((name, _)) = samples.iter().find(|(_, traffic)| traffic.stalled) {
+        (signal, Verdict::Direct, format!("{} stalled", name))
+    } else {
+        let detail = "no traffic sampled".to_string();
+        (signal, Verdict::Inconclusive, detail)
+    }
+}
+
+fn public_ip(public_ip: Option<IpAddr>, exit_ranges: &[IpNet]) -> (Signal, Verdict, String) {
+    let signal = Signal::PublicIp;
+    match public_ip {
+        Some(address) if !exit_ranges.is_empty() => {
+            if exit_ranges.iter().any(|range| range.contains(&address)) {
+                (
+                    signal,
+                    Verdict::Tunneled,
+                    format!("{} in exit ranges", address),
+                )
+            } else {
+                (
+                    signal,
+                    Verdict::Direct,
+                    format!("{} outside of exit ranges", address),
+                )
+            }
+        }
+        Some(address) => (signal, Verdict::Inconclusive, format!("{}", address)),
+        None => (signal, Verdict::Inconclusive, "not looked up".to_string()),
+    }
+}
+
+fn process(detection: &Detection) -> (Signal, Verdict, String) {
+    let signal = Signal::Process;
+    // kernel tunnels run without a process, and daemons run while disconnected
+    let clients: Vec<String> = detection
+        .clients
+        .iter()
+        .filter(|client| !client.daemon && !client.kind.is_mesh())
+        .map(|client| format!("{} ({})", client.name, client.pid))
+        .collect();
+    if clients.is_empty() {
+        let detail = "no vpn clients".to_string();
+        (signal, Verdict::Inconclusive, detail)
+    } else {
+        (signal, Verdict::Tunneled, clients.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kind::Tunnel;
+    use crate::routes::RouteMatch;
+
+    fn detection(status: VpnStatus, default: &str) -> Detection {
+        let mut detection = Detection::new(status);
+        detection.route = Some(RouteMatch {
+            route: Route::new("0.0.0.0/0".parse().unwrap(), default),
+            rule: None,
+        });
+        detection
+    }
+
+    fn wireguard() -> Tunnel {
+        Tunnel {
+            name: "wg0".to_string(),
+            kind: TunnelKind::WireGuard,
+            mesh: false,
+        }
+    }
+
+    #[test]
+    fn agreeing_signals() {
+        let mut detection = detection(VpnStatus::Enabled, "wg0");
+        detection.tunnels = vec![wireguard()];
+        let routes = vec![Route::new("0.0.0.0/0".parse().unwrap(), "wg0")];
+        let exit_ranges = vec!["185.65.134.0/24".parse().unwrap()];
+        let public_ip = Some("185.65.134.7".parse().unwrap());
+
+        let report = StatusReport::new(detection, &routes, public_ip, &exit_ranges);
+        assert_eq!(report.status, VpnStatus::Enabled);
+        assert_eq!(report.confidence, 1.0);
+        let verdicts: Vec<Verdict> = report.signals.iter().map(|s| s.verdict).collect();
+        assert_eq!(
+            verdicts,
+            vec![
+                Verdict::Tunneled,
+                Verdict::Tunneled,
+                Verdict::Tunneled,
+                Verdict::Inconclusive,
+                Verdict::Tunneled,
+                Verdict::Inconclusive,
+            ]
+        );
+        assert_eq!(report.signals[3].agrees, None);
+    }
+
+    #[test]
+    fn contradicting_signals() {
+        // the default route goes through the tunnel, but the exit is not the VPN
+        let mut detection = detection(VpnStatus::Enabled, "tun0");
+        detection.tunnels = vec![Tunnel {
+            name: "tun0".to_string(),
+            kind: TunnelKind::Unknown,
+            mesh: false,
+        }];
+        let exit_ranges = vec!["185.65.134.0/24".parse().unwrap()];
+        let public_ip = Some("203.0.113.9".parse().unwrap());
+
+        let report = StatusReport::new(detection, &[], public_ip, &exit_ranges);
+        assert_eq!(report.signals[1].verdict, Verdict::Direct);
+        assert_eq!(report.signals[2].verdict, Verdict::Inconclusive);
+        assert_eq!(report.signals[4].agrees, Some(false));
+        assert_eq!(report.confidence, 3.0 / 8.0);
+    }
+
+    #[test]
+    fn split_tunnel() {
+        let mut detection = detection(VpnStatus::SplitTunnel, "eth0");
+        detection.tunnels = vec![wireguard()];
+        let routes = vec![
+            Route::new("0.0.0.0/0".parse().unwrap(), "eth0"),
+            Route::new("10.0.0.0/8".parse().unwrap(), "wg0"),
+        ];
+
+        let report = StatusReport::new(detection, &routes, None, &[]);
+        assert_eq!(report.signals[0].verdict, Verdict::Direct);
+        assert_eq!(report.signals[0].agrees, Some(true));
+        assert_eq!(
+            report.signals[1].detail,
+            "1 tunnel routes, 0 ipsec policies"
+        );
+        assert_eq!(report.confidence, 1.0);
+    }
+
+    #[test]
+    fn connecting() {
+        let mut detection = detection(VpnStatus::Connecting, "eth0");
+        detection.clients = vec![crate::process::Client {
+            pid: 812,
+            name: "openvpn".to_string(),
+            kind: TunnelKind::OpenVpn,
+            daemon: false,
+            interface: None,
+        }];
+
+        let report = StatusReport::new(detection, &[], None, &[]);
+        assert_eq!(report.signals[5].verdict, Verdict::Tunneled);
+        assert_eq!(report.signals[5].detail, "openvpn (812)");
+        assert_eq!(report.confidence, 1.0);
+    }
+
+    #[test]
+    fn offline() {
+        let report = StatusReport::new(Detection::new(VpnStatus::Offline), &[], None, &[]);
+        assert_eq!(report.confidence, 1.0);
+        assert!(report.signals.iter().all(|signal| signal.agrees.is_none()));
+    }
+}
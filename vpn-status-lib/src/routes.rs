@@ -0,0 +1,634 @@
+use crate::rules::{self, Rule, RuleAction};
+use netdev::ipnet::{IpNet, Ipv4Net, Ipv6Net};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::Path;
+
+const RTF_UP: u32 = 0x0001;
+const RTF_REJECT: u32 = 0x0200;
+const RTF_LOCAL: u32 = 0x8000_0000;
+
+/// Main routing table
+pub const RT_TABLE_MAIN: u32 = 254;
+/// Default routing table
+pub const RT_TABLE_DEFAULT: u32 = 253;
+/// Local routing table, holding the routes to the addresses of the host
+pub const RT_TABLE_LOCAL: u32 = 255;
+
+/// IP address family
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AddressFamily {
+    Ipv4,
+    Ipv6,
+}
+
+impl AddressFamily {
+    pub fn of(address: &IpAddr) -> Self {
+        match address {
+            IpAddr::V4(_) => Self::Ipv4,
+            IpAddr::V6(_) => Self::Ipv6,
+        }
+    }
+
+    /// The unspecified address of the family, i.e. the default route destination
+    pub fn unspecified(&self) -> IpAddr {
+        match self {
+            Self::Ipv4 => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            Self::Ipv6 => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+        }
+    }
+}
+
+impl Display for AddressFamily {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::Ipv4 => write!(f, "ipv4"),
+            Self::Ipv6 => write!(f, "ipv6"),
+        }
+    }
+}
+
+/// Type of a route, what happens to the traffic it matches
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RouteKind {
+    /// forwarded to the egress interface
+    #[default]
+    Unicast,
+    /// silently discarded
+    Blackhole,
+    /// rejected as unreachable
+    Unreachable,
+    /// rejected as administratively prohibited
+    Prohibit,
+    /// looked up by the next policy rule instead
+    Throw,
+}
+
+/// Routing table entry
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Route {
+    /// destination network
+    pub destination: IpNet,
+    /// next hop, if the destination is not directly connected
+    pub gateway: Option<IpAddr>,
+    /// name of the egress interface, empty for routes which do not forward traffic
+    pub interface: String,
+    /// route priority, lower is preferred
+    pub metric: u32,
+    /// routing table the route belongs to
+    #[serde(default = "main_table")]
+    pub table: u32,
+    #[serde(default)]
+    pub kind: RouteKind,
+}
+
+fn main_table() -> u32 {
+    RT_TABLE_MAIN
+}
+
+impl Route {
+    pub fn new(destination: IpNet, interface: &str) -> Self {
+        Self {
+            destination,
+            gateway: None,
+            interface: interface.to_string(),
+            metric: 0,
+            table: RT_TABLE_MAIN,
+            kind: RouteKind::Unicast,
+        }
+    }
+}
+
+/// Route selected for a destination, with the policy rule that selected it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RouteMatch {
+    pub route: Route,
+    /// `None` when no policy rules are available and the main table was used
+    pub rule: Option<Rule>,
+}
+
+/// Read the IPv4 and IPv6 routing tables from a procfs `net` directory, e.g. `/proc/net`
+pub fn read_proc_routes(proc_net: &Path) -> Vec<Route> {
+    let mut routes = vec![];
+    if let Ok(content) = std::fs::read_to_string(proc_net.join("route")) {
+        routes.extend(parse_ipv4_routes(&content));
+    }
+    if let Ok(content) = std::fs::read_to_string(proc_net.join("ipv6_route")) {
+        routes.extend(parse_ipv6_routes(&content));
+    }
+    routes
+}
+
+/// Parse the contents of `/proc/net/route`
+pub fn parse_ipv4_routes(content: &str) -> Vec<Route> {
+    let mut routes = vec![];
+    // skip the header line
+    for line in content.lines().skip(1) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 8 {
+            continue;
+        }
+        let (Some(destination), Some(gateway), Some(flags), Ok(metric), Some(mask)) = (
+            parse_hex_ipv4(fields[1]),
+            parse_hex_ipv4(fields[2]),
+            u32::from_str_radix(fields[3], 16).ok(),
+            fields[6].parse::<u32>(),
+            parse_hex_ipv4(fields[7]),
+        ) else {
+            continue;
+        };
+        if flags & RTF_UP == 0 || flags & RTF_REJECT != 0 {
+            continue;
+        }
+        let Ok(destination) = Ipv4Net::with_netmask(destination, mask) else {
+            continue;
+        };
+        routes.push(Route {
+            destination: IpNet::V4(destination),
+            gateway: (!gateway.is_unspecified()).then_some(IpAddr::V4(gateway)),
+            interface: fields[0].to_string(),
+            metric,
+            table: RT_TABLE_MAIN,
+            kind: RouteKind::Unicast,
+        });
+    }
+    routes
+}
+
+/// Parse the contents of `/proc/net/ipv6_route`
+pub fn parse_ipv6_routes(content: &str) -> Vec<Route> {
+    let mut routes = vec![];
+    for line in content.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 10 {
+            continue;
+        }
+        let (Some(destination), Ok(prefix), Some(gateway), Ok(metric), Ok(flags)) = (
+            parse_hex_ipv6(fields[0]),
+            u8::from_str_radix(fields[1], 16),
+            parse_hex_ipv6(fields[4]),
+            u32::from_str_radix(fields[5], 16),
+            u32::from_str_radix(fields[8], 16),
+        ) else {
+            continue;
+        };
+        if flags & RTF_UP == 0 || flags & (RTF_REJECT | RTF_LOCAL) != 0 {
+            continue;
+        }
+        let Ok(destination) = Ipv6Net::new(destination, prefix) else {
+            continue;
+        };
+        routes.push(Route {
+            destination: IpNet::V6(destination),
+            gateway: (!gateway.is_unspecified()).then_some(IpAddr::V6(gateway)),
+            interface: fields[9].to_string(),
+            metric,
+            table: RT_TABLE_MAIN,
+            kind: RouteKind::Unicast,
+        });
+    }
+    routes
+}
+
+/// Addresses in `/proc/net/route` are printed as the in-memory value of the network order bytes
+fn parse_hex_ipv4(hex: &str) -> Option<Ipv4Addr> {
+    let value = u32::from_str_radix(hex, 16).ok()?;
+    Some(Ipv4Addr::from(value.to_ne_bytes()))
+}
+
+fn parse_hex_ipv6(hex: &str) -> Option<Ipv6Addr> {
+    u128::from_str_radix(hex, 16).ok().map(Ipv6Addr::from)
+}
+
+/// Find the route for a destination in a single table by longest prefix match
+pub fn lookup_table(routes: &[Route], table: u32, destination: IpAddr) -> Option<&Route> {
+    routes
+        .iter()
+        .filter(|route| route.table == table && route.destination.contains(&destination))
+        .min_by_key(|route| {
+            (
+                std::cmp::Reverse(route.destination.prefix_len()),
+                route.metric,
+            )
+        })
+}
+
+/// Find the route used for traffic from this host to a destination.
+///
+/// Policy rules are evaluated in priority order, the first rule whose table has a
+/// matching route decides. Without rules only the main and default tables are used.
+/// Blackhole, unreachable and prohibit routes match without an egress, so none is found.
+pub fn lookup(routes: &[Route], rules: &[Rule], destination: IpAddr) -> Option<RouteMatch> {
+    let family = AddressFamily::of(&destination);
+    let mut rules: Vec<Rule> = rules
+        .iter()
+        .filter(|rule| rule.family == family)
+        .cloned()
+        .collect();
+    let implicit = rules.is_empty();
+    if implicit {
+        rules = rules::default_rules(family);
+    }
+    rules.sort_by_key(|rule| rule.priority);
+
+    for rule in rules {
+        if !rule.matches(destination) {
+            continue;
+        }
+        match rule.action {
+            RuleAction::Lookup(table) => {
+                let Some(route) = lookup_table(routes, table, destination) else {
+                    continue;
+                };
+                if rule
+                    .suppress_prefix_length
+                    .is_some_and(|len| route.destination.prefix_len() <= len)
+                {
+                    continue;
+                }
+                match route.kind {
+                    RouteKind::Unicast => (),
+                    RouteKind::Throw => continue,
+                    RouteKind::Blackhole | RouteKind::Unreachable | RouteKind::Prohibit => {
+                        return None
+                    }
+                }
+                return Some(RouteMatch {
+                    route: route.clone(),
+                    rule: (!implicit).then_some(rule),
+                });
+            }
+            RuleAction::Block => return None,
+        }
+    }
+    None
+}
+
+/// Find the default route of an address family, taking policy rules into account
+pub fn default_route(
+    routes: &[Route],
+    rules: &[Rule],
+    family: AddressFamily,
+) -> Option<RouteMatch> {
+    lookup(routes, rules, family.unspecified())
+}
+
+/// Destinations spread across the public address space, one per /8 block for IPv4
+/// and one per /8 block of the global unicast range for IPv6.
+pub(crate) fn public_samples(family: AddressFamily) -> Vec<IpAddr> {
+    match family {
+        AddressFamily::Ipv6 => (0x20..0x40u16)
+            .map(|block| IpAddr::V6(Ipv6Addr::new(block << 8, 0, 0, 0, 0, 0, 0, 1)))
+            .collect(),
+        AddressFamily::Ipv4 => (1..224u8)
+            .filter(|block| *block != 10 && *block != 127)
+            .map(|block| IpAddr::V4(Ipv4Addr::new(block, 0, 0, 1)))
+            .collect(),
+    }
+}
+
+/// Check whether traffic to the whole public address space of an address family
+/// egresses through one of the given interfaces.
+///
+/// This catches tunnels which keep the original default route in place and override it
+/// with more specific routes, like OpenVPN's `redirect-gateway def1`, or with policy
+/// routing, like `wg-quick`.
+pub fn covers_public_space(
+    routes: &[Route],
+    rules: &[Rule],
+    interfaces: &[String],
+    family: AddressFamily,
+) -> bool {
+    public_samples(family).into_iter().all(|destination| {
+        lookup(routes, rules, destination)
+            .is_some_and(|found| interfaces.contains(&found.route.interface))
+    })
+}
+
+/// Check whether traffic to any public destination of an address family
+/// egresses outside of the given interfaces.
+pub fn bypasses_interfaces(
+    routes: &[Route],
+    rules: &[Rule],
+    interfaces: &[String],
+    family: AddressFamily,
+) -> bool {
+    public_samples(family).into_iter().any(|destination| {
+        lookup(routes, rules, destination)
+            .is_some_and(|found| !interfaces.contains(&found.route.interface))
+    })
+}
+
+/// Ranges used by overlay networks: shared address space (CGNAT) and unique local addresses
+const OVERLAY_RANGES: &[&str] = &["100.64.0.0/10", "fc00::/7"];
+
+/// Ranges which are not reachable on the public internet
+const NON_PUBLIC_RANGES: &[&str] = &[
+    "0.0.0.0/8",
+    "10.0.0.0/8",
+    "100.64.0.0/10",
+    "127.0.0.0/8",
+    "169.254.0.0/16",
+    "172.16.0.0/12",
+    "192.168.0.0/16",
+    "224.0.0.0/4",
+    "255.255.255.255/32",
+    "::1/128",
+    "fc00::/7",
+    "fe80::/10",
+    "ff00::/8",
+];
+
+fn within(ranges: &[&str], net: &IpNet) -> bool {
+    ranges.iter().any(|range| {
+        range
+            .parse::<IpNet>()
+            .is_ok_and(|range| range.contains(net))
+    })
+}
+
+/// Check whether an address belongs to the ranges used by overlay networks
+pub fn is_overlay_address(address: &IpAddr) -> bool {
+    within(OVERLAY_RANGES, &IpNet::from(*address))
+}
+
+/// Check whether any route through the interface, in any table, reaches public destinations
+pub fn routes_public_destinations(routes: &[Route], interface: &str) -> bool {
+    routes
+        .iter()
+        .any(|route| route.interface == interface && !within(NON_PUBLIC_RANGES, &route.destination))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(destination: &str, interface: &str) -> Route {
+        Route::new(destination.parse().unwrap(), interface)
+    }
+
+    const PROC_ROUTE: &str = "\
+Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT
+eth0\t00000000\t0101A8C0\t0003\t0\t0\t100\t00000000\t0\t0\t0
+tun0\t00000000\t0100080A\t0003\t0\t0\t0\t00000080\t0\t0\t0
+tun0\t00000080\t0100080A\t0003\t0\t0\t0\t00000080\t0\t0\t0
+tun0\t0000080A\t00000000\t0001\t0\t0\t0\t00FFFFFF\t0\t0\t0
+eth0\t0001A8C0\t00000000\t0001\t0\t0\t100\t00FFFFFF\t0\t0\t0
+eth0\t0A0B0C0D\t0101A8C0\t0003\t0\t0\t100\tFFFFFFFF\t0\t0\t0
+";
+
+    const PROC_IPV6_ROUTE: &str = "\
+20010db8000000000000000000000000 40 00000000000000000000000000000000 00 00000000000000000000000000000000 00000100 00000001 00000000 00000001     eth0
+00000000000000000000000000000000 00 00000000000000000000000000000000 00 fe800000000000000000000000000001 00000400 00000001 00000000 00000003     eth0
+00000000000000000000000000000001 80 00000000000000000000000000000000 00 00000000000000000000000000000000 00000000 00000002 00000000 80200001       lo
+00000000000000000000000000000000 00 00000000000000000000000000000000 00 00000000000000000000000000000000 ffffffff 00000001 00000000 00200200       lo
+";
+
+    #[test]
+    fn parse_ipv4() {
+        let routes = parse_ipv4_routes(PROC_ROUTE);
+        assert_eq!(routes.len(), 6);
+        assert_eq!(
+            routes[0],
+            Route {
+                destination: "0.0.0.0/0".parse().unwrap(),
+                gateway: Some("192.168.1.1".parse().unwrap()),
+                interface: "eth0".to_string(),
+                metric: 100,
+                table: RT_TABLE_MAIN,
+                kind: RouteKind::Unicast,
+            }
+        );
+        assert_eq!(routes[1].destination, "0.0.0.0/1".parse::<IpNet>().unwrap());
+        assert_eq!(
+            routes[2].destination,
+            "128.0.0.0/1".parse::<IpNet>().unwrap()
+        );
+        assert_eq!(routes[3].gateway, None);
+        assert_eq!(
+            routes[5].destination,
+            "13.12.11.10/32".parse::<IpNet>().unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_ipv6() {
+        let routes = parse_ipv6_routes(PROC_IPV6_ROUTE);
+        assert_eq!(routes.len(), 2);
+        assert_eq!(
+            routes[0].destination,
+            "2001:db8::/64".parse::<IpNet>().unwrap()
+        );
+        assert_eq!(routes[0].gateway, None);
+        assert_eq!(routes[1].destination, "::/0".parse::<IpNet>().unwrap());
+        assert_eq!(routes[1].gateway, Some("fe80::1".parse().unwrap()));
+        assert_eq!(routes[1].metric, 1024);
+    }
+
+    #[test]
+    fn longest_prefix_match() {
+        let routes = parse_ipv4_routes(PROC_ROUTE);
+        let found = lookup(&routes, &[], "1.1.1.1".parse().unwrap()).unwrap();
+        assert_eq!(found.route.interface, "tun0");
+        assert_eq!(found.rule, None);
+        let found = lookup(&routes, &[], "192.168.1.20".parse().unwrap()).unwrap();
+        assert_eq!(found.route.interface, "eth0");
+        let found = lookup(&routes, &[], "13.12.11.10".parse().unwrap()).unwrap();
+        assert_eq!(found.route.interface, "eth0");
+        let routes = parse_ipv6_routes(PROC_IPV6_ROUTE);
+        assert!(lookup(&routes, &[], "1.1.1.1".parse().unwrap()).is_none());
+    }
+
+    #[test]
+    fn lowest_metric_wins() {
+        let mut eth0 = route("0.0.0.0/0", "eth0");
+        eth0.metric = 600;
+        let mut wlan0 = route("0.0.0.0/0", "wlan0");
+        wlan0.metric = 100;
+        let routes = vec![eth0, wlan0];
+        let found = lookup(&routes, &[], "1.1.1.1".parse().unwrap()).unwrap();
+        assert_eq!(found.route.interface, "wlan0");
+    }
+
+    #[test]
+    fn rejecting_routes() {
+        let mut blackhole = route("0.0.0.0/0", "");
+        blackhole.kind = RouteKind::Blackhole;
+        blackhole.metric = 1000;
+        let mut unreachable = route("10.0.0.0/8", "");
+        unreachable.kind = RouteKind::Unreachable;
+        let routes = vec![blackhole, unreachable, route("0.0.0.0/1", "tun0")];
+        let found = lookup(&routes, &[], "1.1.1.1".parse().unwrap()).unwrap();
+        assert_eq!(found.route.interface, "tun0");
+        // the kill switch stops the traffic the tunnel does not carry
+        assert!(lookup(&routes, &[], "200.1.1.1".parse().unwrap()).is_none());
+        assert!(lookup(&routes, &[], "10.1.1.1".parse().unwrap()).is_none());
+
+        // a throw route continues with the next rule
+        let mut throw = route("0.0.0.0/0", "");
+        throw.kind = RouteKind::Throw;
+        throw.table = 51820;
+        let mut default = route("0.0.0.0/0", "eth0");
+        default.table = 100;
+        let rules = vec![
+            Rule::new(AddressFamily::Ipv4, 1, RuleAction::Lookup(51820)),
+            Rule::new(AddressFamily::Ipv4, 2, RuleAction::Lookup(100)),
+        ];
+        let found = lookup(&[throw, default], &rules, "1.1.1.1".parse().unwrap()).unwrap();
+        assert_eq!(found.route.interface, "eth0");
+    }
+
+    #[test]
+    fn overlay_routes() {
+        let mut routes = vec![
+            route("100.64.0.0/10", "tailscale0"),
+            route("fd7a:115c:a1e0::/48", "tailscale0"),
+            route("10.147.17.0/24", "zt0"),
+            route("0.0.0.0/1", "tun0"),
+            route("192.168.1.0/24", "eth0"),
+        ];
+        routes[0].table = 52;
+        assert!(!routes_public_destinations(&routes, "tailscale0"));
+        assert!(!routes_public_destinations(&routes, "zt0"));
+        assert!(routes_public_destinations(&routes, "tun0"));
+
+        // exit node
+        routes.push(route("0.0.0.0/0", "tailscale0"));
+        assert!(routes_public_destinations(&routes, "tailscale0"));
+
+        assert!(is_overlay_address(&"100.101.102.103".parse().unwrap()));
+        assert!(is_overlay_address(&"fd7a:115c:a1e0::1".parse().unwrap()));
+        assert!(!is_overlay_address(&"10.8.0.2".parse().unwrap()));
+    }
+
+    #[test]
+    fn def1_covers_public_space() {
+        let routes = parse_ipv4_routes(PROC_ROUTE);
+        let tunnels = vec!["tun0".to_string()];
+        assert!(covers_public_space(
+            &routes,
+            &[],
+            &tunnels,
+            AddressFamily::Ipv4
+        ));
+        assert!(!covers_public_space(
+            &routes,
+            &[],
+            &tunnels,
+            AddressFamily::Ipv6
+        ));
+    }
+
+    #[test]
+    fn split_routes_do_not_cover_public_space() {
+        let routes = vec![
+            route("0.0.0.0/0", "eth0"),
+            route("10.0.0.0/8", "tun0"),
+            route("1.0.0.0/8", "tun0"),
+        ];
+        let tunnels = vec!["tun0".to_string()];
+        assert!(!covers_public_space(
+            &routes,
+            &[],
+            &tunnels,
+            AddressFamily::Ipv4
+        ));
+        assert!(!covers_public_space(
+            &[],
+            &[],
+            &tunnels,
+            AddressFamily::Ipv4
+        ));
+    }
+
+    #[test]
+    fn ipv6_bypasses_ipv4_tunnel() {
+        let routes = vec![
+            route("0.0.0.0/0", "tun0"),
+            route("::/0", "eth0"),
+            route("fd00::/64", "tun0"),
+        ];
+        let tunnels = vec!["tun0".to_string()];
+        assert!(!bypasses_interfaces(
+            &routes,
+            &[],
+            &tunnels,
+            AddressFamily::Ipv4
+        ));
+        assert!(bypasses_interfaces(
+            &routes,
+            &[],
+            &tunnels,
+            AddressFamily::Ipv6
+        ));
+        // unreachable destinations do not bypass the tunnel
+        assert!(!bypasses_interfaces(
+            &[],
+            &[],
+            &tunnels,
+            AddressFamily::Ipv6
+        ));
+    }
+
+    fn wg_quick() -> (Vec<Route>, Vec<Rule>) {
+        let mut wg0 = route("0.0.0.0/0", "wg0");
+        wg0.table = 51820;
+        let routes = vec![
+            route("0.0.0.0/0", "eth0"),
+            route("192.168.1.0/24", "eth0"),
+            wg0,
+        ];
+
+        let family = AddressFamily::Ipv4;
+        let mut suppress = Rule::new(family, 32764, RuleAction::Lookup(RT_TABLE_MAIN));
+        suppress.suppress_prefix_length = Some(0);
+        let mut fwmark = Rule::new(family, 32765, RuleAction::Lookup(51820));
+        fwmark.fwmark = Some(0xca6c);
+        fwmark.invert = true;
+        let rules = vec![
+            Rule::new(family, 0, RuleAction::Lookup(255)),
+            suppress,
+            fwmark,
+            Rule::new(family, 32766, RuleAction::Lookup(RT_TABLE_MAIN)),
+            Rule::new(family, 32767, RuleAction::Lookup(RT_TABLE_DEFAULT)),
+        ];
+        (routes, rules)
+    }
+
+    #[test]
+    fn policy_routing() {
+        let (routes, rules) = wg_quick();
+        let found = default_route(&routes, &rules, AddressFamily::Ipv4).unwrap();
+        assert_eq!(found.route.interface, "wg0");
+        assert_eq!(found.route.table, 51820);
+        assert_eq!(found.rule.unwrap().priority, 32765);
+
+        // more specific routes of the main table are not suppressed
+        let found = lookup(&routes, &rules, "192.168.1.20".parse().unwrap()).unwrap();
+        assert_eq!(found.route.interface, "eth0");
+        assert_eq!(found.rule.unwrap().priority, 32764);
+
+        let tunnels = vec!["wg0".to_string()];
+        assert!(covers_public_space(
+            &routes,
+            &rules,
+            &tunnels,
+            AddressFamily::Ipv4
+        ));
+        // without the rules only the main table is used
+        assert!(!covers_public_space(
+            &routes,
+            &[],
+            &tunnels,
+            AddressFamily::Ipv4
+        ));
+    }
+
+    #[test]
+    fn blocking_rule() {
+        let (routes, mut rules) = wg_quick();
+        rules.push(Rule::new(AddressFamily::Ipv4, 1, RuleAction::Block));
+        assert!(default_route(&routes, &rules, AddressFamily::Ipv4).is_none());
+    }
+}
@@ -0,0 +1,135 @@
+use crate::routes::{AddressFamily, RT_TABLE_DEFAULT, RT_TABLE_MAIN};
+use netdev::ipnet::IpNet;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+
+/// What happens to traffic matching a rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RuleAction {
+    /// look up the route in the given table
+    Lookup(u32),
+    /// drop the traffic (blackhole, unreachable or prohibit)
+    Block,
+}
+
+/// Routing policy rule, as listed by `ip rule`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rule {
+    /// address family the rule applies to
+    pub family: AddressFamily,
+    /// rules are evaluated in ascending priority order
+    pub priority: u32,
+    /// action taken when the rule matches
+    pub action: RuleAction,
+    /// source address selector
+    pub source: Option<IpNet>,
+    /// destination address selector
+    pub destination: Option<IpNet>,
+    /// firewall mark selector
+    pub fwmark: Option<u32>,
+    /// mask applied to the firewall mark, all bits by default
+    pub fwmask: Option<u32>,
+    /// input interface selector
+    pub input_interface: Option<String>,
+    /// output interface selector
+    pub output_interface: Option<String>,
+    /// ignore routes with a prefix length less than or equal to this value
+    pub suppress_prefix_length: Option<u8>,
+    /// match traffic that does not match the selectors
+    pub invert: bool,
+}
+
+impl Rule {
+    pub fn new(family: AddressFamily, priority: u32, action: RuleAction) -> Self {
+        Self {
+            family,
+            priority,
+            action,
+            source: None,
+            destination: None,
+            fwmark: None,
+            fwmask: None,
+            input_interface: None,
+            output_interface: None,
+            suppress_prefix_length: None,
+            invert: false,
+        }
+    }
+
+    /// Check whether the rule applies to unmarked traffic originating from this host.
+    pub fn matches(&self, destination: IpAddr) -> bool {
+        if self.family != AddressFamily::of(&destination) {
+            return false;
+        }
+        // the source address is not known yet when local traffic is routed,
+        // so only catch-all source selectors match
+        let selected = self
+            .destination
+            .is_none_or(|net| net.contains(&destination))
+            && self.source.is_none_or(|net| net.prefix_len() == 0)
+            && self
+                .input_interface
+                .as_deref()
+                .is_none_or(|name| name == "lo")
+            && self.output_interface.is_none()
+            && self
+                .fwmark
+                .is_none_or(|mark| mark & self.fwmask.unwrap_or(u32::MAX) == 0);
+        selected != self.invert
+    }
+}
+
+/// Rules used when a source does not provide any, equivalent to the kernel defaults
+pub(crate) fn default_rules(family: AddressFamily) -> Vec<Rule> {
+    vec![
+        Rule::new(family, 32766, RuleAction::Lookup(RT_TABLE_MAIN)),
+        Rule::new(family, 32767, RuleAction::Lookup(RT_TABLE_DEFAULT)),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wg_quick_rule() -> Rule {
+        let mut rule = Rule::new(AddressFamily::Ipv4, 32765, RuleAction::Lookup(51820));
+        rule.fwmark = Some(0xca6c);
+        rule.invert = true;
+        rule
+    }
+
+    #[test]
+    fn inverted_fwmark_matches_unmarked_traffic() {
+        let rule = wg_quick_rule();
+        assert!(rule.matches("1.1.1.1".parse().unwrap()));
+        assert!(!rule.matches("2606:4700::1111".parse().unwrap()));
+    }
+
+    #[test]
+    fn fwmark_does_not_match_unmarked_traffic() {
+        let mut rule = wg_quick_rule();
+        rule.invert = false;
+        assert!(!rule.matches("1.1.1.1".parse().unwrap()));
+        // marks outside of the mask are ignored
+        rule.fwmask = Some(0xff0000);
+        assert!(rule.matches("1.1.1.1".parse().unwrap()));
+    }
+
+    #[test]
+    fn selectors() {
+        let mut rule = Rule::new(AddressFamily::Ipv4, 100, RuleAction::Lookup(100));
+        rule.destination = Some("10.0.0.0/8".parse().unwrap());
+        assert!(rule.matches("10.1.2.3".parse().unwrap()));
+        assert!(!rule.matches("1.1.1.1".parse().unwrap()));
+
+        let mut rule = Rule::new(AddressFamily::Ipv4, 100, RuleAction::Lookup(100));
+        rule.source = Some("192.168.1.0/24".parse().unwrap());
+        assert!(!rule.matches("1.1.1.1".parse().unwrap()));
+
+        let mut rule = Rule::new(AddressFamily::Ipv4, 100, RuleAction::Lookup(100));
+        rule.input_interface = Some("eth0".to_string());
+        assert!(!rule.matches("1.1.1.1".parse().unwrap()));
+        rule.input_interface = Some("lo".to_string());
+        assert!(rule.matches("1.1.1.1".parse().unwrap()));
+    }
+}
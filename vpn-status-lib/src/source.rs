@@ -0,0 +1,773 @@
+use crate::config::Config;
+use crate::dns::{DnsInspector, Nameserver};
+use crate::error::VpnStatusError;
+use crate::glob;
+use crate::ipsec;
+use crate::kind::{self, LinkInfo};
+use crate::networkmanager::Connection;
+use crate::process::Client;
+use crate::routes::Route;
+use crate::rules::Rule;
+use crate::traffic::Counters;
+use crate::wireguard;
+#[cfg(target_os = "linux")]
+use log::debug;
+use netdev::interface::InterfaceType;
+use netdev::Interface;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Source of network interface state used to determine the VPN status
+pub trait InterfaceSource {
+    /// Get the default interface
+    fn default_interface(&self) -> Result<Interface, String>;
+    /// Get all network interfaces
+    fn interfaces(&self) -> Vec<Interface>;
+    /// Get the routes of all routing tables, empty if they are not available
+    fn routes(&self) -> Vec<Route> {
+        Vec::new()
+    }
+    /// Get the routing policy rules, empty if they are not available
+    fn rules(&self) -> Vec<Rule> {
+        Vec::new()
+    }
+    /// Get the active nameservers, empty if they are not available
+    fn nameservers(&self) -> Vec<Nameserver> {
+        Vec::new()
+    }
+    /// Get the link layer details of an interface
+    fn link_info(&self, _name: &str) -> Option<LinkInfo> {
+        None
+    }
+    /// Get the byte counters of an interface
+    fn counters(&self, _name: &str) -> Option<Counters> {
+        None
+    }
+    /// Get the peers of a WireGuard interface, empty for other interfaces
+    fn wireguard_peers(&self, _name: &str) -> Vec<wireguard::Peer> {
+        Vec::new()
+    }
+    /// Get the file storing the state between runs, none if it should not be persisted
+    fn state_path(&self) -> Option<PathBuf> {
+        None
+    }
+    /// Get the IPsec policies, empty if they are not available
+    fn ipsec_policies(&self) -> Vec<ipsec::Policy> {
+        Vec::new()
+    }
+    /// Get the IPsec security associations, empty if they are not available
+    fn ipsec_states(&self) -> Vec<ipsec::State> {
+        Vec::new()
+    }
+    /// Get the running VPN client processes, empty if they are not available
+    fn clients(&self) -> Vec<Client> {
+        Vec::new()
+    }
+    /// Get the VPN connections managed by NetworkManager, empty if they are not available
+    fn connections(&self) -> Vec<Connection> {
+        Vec::new()
+    }
+    /// Get the SSID a wireless interface is connected to, none for other interfaces
+    fn ssid(&self, _name: &str) -> Option<String> {
+        None
+    }
+    /// Get the routes of all routing tables, failing when they can not be read
+    fn try_routes(&self) -> Result<Vec<Route>, VpnStatusError> {
+        Ok(self.routes())
+    }
+    /// Get the routing policy rules, failing when they can not be read
+    fn try_rules(&self) -> Result<Vec<Rule>, VpnStatusError> {
+        Ok(self.rules())
+    }
+    /// Get the peers of a WireGuard interface, failing when they can not be read
+    fn try_wireguard_peers(&self, name: &str) -> Result<Vec<wireguard::Peer>, VpnStatusError> {
+        Ok(self.wireguard_peers(name))
+    }
+    /// Get the IPsec policies, failing when they can not be read
+    fn try_ipsec_policies(&self) -> Result<Vec<ipsec::Policy>, VpnStatusError> {
+        Ok(self.ipsec_policies())
+    }
+    /// Get the IPsec security associations, failing when they can not be read
+    fn try_ipsec_states(&self) -> Result<Vec<ipsec::State>, VpnStatusError> {
+        Ok(self.ipsec_states())
+    }
+}
+
+/// Interface source reading the state of the host with netdev
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NetdevSource;
+
+#[cfg(target_os = "linux")]
+impl NetdevSource {
+    /// Read the routes of all tables over netlink
+    pub fn read_routes(&self) -> Result<Vec<Route>, VpnStatusError> {
+        use crate::routes::AddressFamily;
+
+        let mut routes = vec![];
+        for family in [AddressFamily::Ipv4, AddressFamily::Ipv6] {
+            let family_routes = crate::netlink::read_routes(family)
+                .map_err(|error| VpnStatusError::backend("routes", error))?;
+            routes.extend(family_routes);
+        }
+        Ok(routes)
+    }
+
+    /// Read the routing policy rules over netlink
+    pub fn read_rules(&self) -> Result<Vec<Rule>, VpnStatusError> {
+        use crate::routes::AddressFamily;
+
+        let mut rules = vec![];
+        for family in [AddressFamily::Ipv4, AddressFamily::Ipv6] {
+            let family_rules = crate::netlink::read_rules(family)
+                .map_err(|error| VpnStatusError::backend("rules", error))?;
+            rules.extend(family_rules);
+        }
+        Ok(rules)
+    }
+
+    /// Read the peers of a WireGuard device, which requires `CAP_NET_ADMIN`
+    pub fn read_wireguard_peers(&self, name: &str) -> Result<Vec<wireguard::Peer>, VpnStatusError> {
+        crate::netlink::read_wireguard_peers(name)
+            .map_err(|error| VpnStatusError::backend("wireguard peers", error))
+    }
+
+    /// Read the IPsec policies, which requires `CAP_NET_ADMIN`
+    pub fn read_ipsec_policies(&self) -> Result<Vec<ipsec::Policy>, VpnStatusError> {
+        crate::netlink::read_xfrm_policies()
+            .map_err(|error| VpnStatusError::backend("ipsec policies", error))
+    }
+
+    /// Read the IPsec security associations, which requires `CAP_NET_ADMIN`
+    pub fn read_ipsec_states(&self) -> Result<Vec<ipsec::State>, VpnStatusError> {
+        crate::netlink::read_xfrm_states()
+            .map_err(|error| VpnStatusError::backend("ipsec states", error))
+    }
+
+    /// Read the SSID of a wireless interface over nl80211, none while it is not associated
+    pub fn read_ssid(&self, name: &str) -> Result<Option<String>, VpnStatusError> {
+        crate::netlink::read_ssid(name).map_err(|error| VpnStatusError::backend("nl80211", error))
+    }
+}
+
+impl InterfaceSource for NetdevSource {
+    fn default_interface(&self) -> Result<Interface, String> {
+        netdev::get_default_interface()
+    }
+
+    fn interfaces(&self) -> Vec<Interface> {
+        netdev::get_interfaces()
+    }
+
+    #[cfg(target_os = "linux")]
+    fn routes(&self) -> Vec<Route> {
+        self.read_routes().unwrap_or_else(|error| {
+            // procfs only lists the main table
+            debug!("{}, falling back to procfs", error);
+            crate::routes::read_proc_routes(std::path::Path::new("/proc/net"))
+        })
+    }
+
+    #[cfg(target_os = "linux")]
+    fn rules(&self) -> Vec<Rule> {
+        use crate::netlink;
+        use crate::routes::AddressFamily;
+
+        // the rules of one address family are still useful without the other one
+        let mut rules = vec![];
+        for family in [AddressFamily::Ipv4, AddressFamily::Ipv6] {
+            match netlink::read_rules(family) {
+                Ok(family_rules) => rules.extend(family_rules),
+                Err(error) => debug!("failed reading {} rules over netlink: {}", family, error),
+            }
+        }
+        rules
+    }
+
+    fn nameservers(&self) -> Vec<Nameserver> {
+        DnsInspector::default().nameservers(&self.interfaces())
+    }
+
+    #[cfg(target_os = "linux")]
+    fn link_info(&self, name: &str) -> Option<LinkInfo> {
+        crate::kind::read_link_info(std::path::Path::new("/sys/class/net"), name)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn counters(&self, name: &str) -> Option<Counters> {
+        crate::traffic::read_counters(std::path::Path::new("/sys/class/net"), name)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn wireguard_peers(&self, name: &str) -> Vec<wireguard::Peer> {
+        self.read_wireguard_peers(name).unwrap_or_else(|error| {
+            debug!("{} of {}", error, name);
+            vec![]
+        })
+    }
+
+    #[cfg(target_os = "linux")]
+    fn ipsec_policies(&self) -> Vec<ipsec::Policy> {
+        self.read_ipsec_policies().unwrap_or_else(|error| {
+            debug!("{}", error);
+            vec![]
+        })
+    }
+
+    #[cfg(target_os = "linux")]
+    fn ipsec_states(&self) -> Vec<ipsec::State> {
+        self.read_ipsec_states().unwrap_or_else(|error| {
+            debug!("{}", error);
+            vec![]
+        })
+    }
+
+    #[cfg(target_os = "linux")]
+    fn clients(&self) -> Vec<Client> {
+        crate::process::scan(std::path::Path::new("/proc"))
+    }
+
+    #[cfg(feature = "networkmanager")]
+    fn connections(&self) -> Vec<Connection> {
+        crate::networkmanager::active_connections().unwrap_or_else(|error| {
+            log::debug!("failed reading networkmanager connections: {}", error);
+            vec![]
+        })
+    }
+
+    #[cfg(target_os = "linux")]
+    fn try_routes(&self) -> Result<Vec<Route>, VpnStatusError> {
+        self.read_routes()
+    }
+
+    #[cfg(target_os = "linux")]
+    fn try_rules(&self) -> Result<Vec<Rule>, VpnStatusError> {
+        self.read_rules()
+    }
+
+    #[cfg(target_os = "linux")]
+    fn try_wireguard_peers(&self, name: &str) -> Result<Vec<wireguard::Peer>, VpnStatusError> {
+        self.read_wireguard_peers(name)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn try_ipsec_policies(&self) -> Result<Vec<ipsec::Policy>, VpnStatusError> {
+        self.read_ipsec_policies()
+    }
+
+    #[cfg(target_os = "linux")]
+    fn try_ipsec_states(&self) -> Result<Vec<ipsec::State>, VpnStatusError> {
+        self.read_ipsec_states()
+    }
+
+    #[cfg(target_os = "linux")]
+    fn ssid(&self, name: &str) -> Option<String> {
+        // only ask nl80211 about interfaces which are wireless
+        let wireless = crate::trust::read_wireless_interfaces(std::path::Path::new("/proc/net"));
+        if !wireless.iter().any(|interface| interface == name) {
+            return None;
+        }
+        self.read_ssid(name).unwrap_or_else(|error| {
+            debug!("{} of {}", error, name);
+            None
+        })
+    }
+}
+
+/// In-memory interface source, for tests or previously captured state
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct MemorySource {
+    pub interfaces: Vec<Interface>,
+    #[serde(default)]
+    pub routes: Vec<Route>,
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+    #[serde(default)]
+    pub nameservers: Vec<Nameserver>,
+    #[serde(default)]
+    pub links: HashMap<String, LinkInfo>,
+    #[serde(default)]
+    pub counters: HashMap<String, Counters>,
+    #[serde(default)]
+    pub wireguard_peers: HashMap<String, Vec<wireguard::Peer>>,
+    #[serde(default)]
+    pub ipsec_policies: Vec<ipsec::Policy>,
+    #[serde(default)]
+    pub ipsec_states: Vec<ipsec::State>,
+    #[serde(default)]
+    pub clients: Vec<Client>,
+    #[serde(default)]
+    pub connections: Vec<Connection>,
+    #[serde(default)]
+    pub ssids: HashMap<String, String>,
+}
+
+impl MemorySource {
+    pub fn new(interfaces: Vec<Interface>) -> Self {
+        Self {
+            interfaces,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_routes(mut self, routes: Vec<Route>) -> Self {
+        self.routes = routes;
+        self
+    }
+
+    pub fn with_rules(mut self, rules: Vec<Rule>) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    pub fn with_nameservers(mut self, nameservers: Vec<Nameserver>) -> Self {
+        self.nameservers = nameservers;
+        self
+    }
+
+    pub fn with_links(mut self, links: HashMap<String, LinkInfo>) -> Self {
+        self.links = links;
+        self
+    }
+
+    pub fn with_counters(mut self, counters: HashMap<String, Counters>) -> Self {
+        self.counters = counters;
+        self
+    }
+
+    pub fn with_wireguard_peers(mut self, peers: HashMap<String, Vec<wireguard::Peer>>) -> Self {
+        self.wireguard_peers = peers;
+        self
+    }
+
+    pub fn with_ipsec(mut self, policies: Vec<ipsec::Policy>, states: Vec<ipsec::State>) -> Self {
+        self.ipsec_policies = policies;
+        self.ipsec_states = states;
+        self
+    }
+
+    pub fn with_clients(mut self, clients: Vec<Client>) -> Self {
+        self.clients = clients;
+        self
+    }
+
+    pub fn with_connections(mut self, connections: Vec<Connection>) -> Self {
+        self.connections = connections;
+        self
+    }
+
+    pub fn with_ssids(mut self, ssids: HashMap<String, String>) -> Self {
+        self.ssids = ssids;
+        self
+    }
+}
+
+impl InterfaceSource for MemorySource {
+    fn default_interface(&self) -> Result<Interface, String> {
+        self.interfaces
+            .iter()
+            .find(|interface| interface.default)
+            .cloned()
+            .ok_or_else(|| String::from("Local IP address not found"))
+    }
+
+    fn interfaces(&self) -> Vec<Interface> {
+        self.interfaces.clone()
+    }
+
+    fn routes(&self) -> Vec<Route> {
+        self.routes.clone()
+    }
+
+    fn rules(&self) -> Vec<Rule> {
+        self.rules.clone()
+    }
+
+    fn nameservers(&self) -> Vec<Nameserver> {
+        self.nameservers.clone()
+    }
+
+    fn link_info(&self, name: &str) -> Option<LinkInfo> {
+        self.links.get(name).cloned()
+    }
+
+    fn counters(&self, name: &str) -> Option<Counters> {
+        self.counters.get(name).copied()
+    }
+
+    fn wireguard_peers(&self, name: &str) -> Vec<wireguard::Peer> {
+        self.wireguard_peers.get(name).cloned().unwrap_or_default()
+    }
+
+    fn ipsec_policies(&self) -> Vec<ipsec::Policy> {
+        self.ipsec_policies.clone()
+    }
+
+    fn ipsec_states(&self) -> Vec<ipsec::State> {
+        self.ipsec_states.clone()
+    }
+
+    fn clients(&self) -> Vec<Client> {
+        self.clients.clone()
+    }
+
+    fn connections(&self) -> Vec<Connection> {
+        self.connections.clone()
+    }
+
+    fn ssid(&self, name: &str) -> Option<String> {
+        self.ssids.get(name).cloned()
+    }
+}
+
+/// Point-to-point interface flag, which makes netdev consider an interface a tunnel
+#[cfg(not(windows))]
+const IFF_POINTOPOINT: u32 = 0x10;
+#[cfg(windows)]
+const IFF_POINTOPOINT: u32 = 0x8;
+const IFF_BROADCAST: u32 = 0x2;
+
+/// Interface source ignoring tunnels which are not VPNs, like container or virtual machine
+/// networks, by their name.
+///
+/// Ignored tunnels are still listed, as regular interfaces. PPP links are always tunnels, while
+/// unencrypted GRE, IPIP and SIT tunnels are only tunnels when enabled.
+///
+/// # Example
+/// ```rust
+/// # use vpn_status_lib::source::{FilteredSource, NetdevSource};
+/// let source = FilteredSource::new(&NetdevSource, vec![], vec!["cni*".to_string()]);
+/// let tunnels = vpn_status_lib::all_tunnel_names_with(&source);
+/// assert!(tunnels.iter().all(|name| !name.starts_with("cni")));
+/// ```
+pub struct FilteredSource<'a> {
+    inner: &'a dyn InterfaceSource,
+    /// name patterns of the interfaces which may be tunnels, all interfaces if empty
+    include: Vec<String>,
+    /// name patterns of the interfaces which are never tunnels
+    exclude: Vec<String>,
+    /// whether GRE, IPIP and SIT tunnels are tunnels
+    ip_tunnels: bool,
+    /// whether only a single tunnel is checked, dropping the clients not known to run it and
+    /// the IPsec policies, which encrypt traffic without a tunnel interface
+    single: bool,
+}
+
+impl<'a> FilteredSource<'a> {
+    pub fn new(inner: &'a dyn InterfaceSource, include: Vec<String>, exclude: Vec<String>) -> Self {
+        Self {
+            inner,
+            include,
+            exclude,
+            ip_tunnels: false,
+            single: false,
+        }
+    }
+
+    pub fn with_ip_tunnels(mut self, ip_tunnels: bool) -> Self {
+        self.ip_tunnels = ip_tunnels;
+        self
+    }
+
+    pub fn with_single(mut self, single: bool) -> Self {
+        self.single = single;
+        self
+    }
+
+    /// Filter the source with the `include_interfaces` and `exclude_interfaces` patterns,
+    /// or keep only the tunnel set in `interface`
+    pub fn from_config(inner: &'a dyn InterfaceSource, config: &Config) -> Self {
+        let include = match config.interface {
+            Some(ref interface) => vec![interface.clone()],
+            None => config.include_interfaces.clone().unwrap_or_default(),
+        };
+        Self::new(
+            inner,
+            include,
+            config.exclude_interfaces.clone().unwrap_or_default(),
+        )
+        .with_ip_tunnels(config.ip_tunnels.unwrap_or_default())
+        .with_single(config.interface.is_some())
+    }
+
+    /// Check whether an interface may be considered a tunnel
+    pub fn allows(&self, name: &str) -> bool {
+        (self.include.is_empty()
+            || self
+                .include
+                .iter()
+                .any(|pattern| glob::matches(pattern, name)))
+            && !self
+                .exclude
+                .iter()
+                .any(|pattern| glob::matches(pattern, name))
+    }
+
+    /// Check whether a VPN client or connection may run one of the tunnels, clients without a
+    /// known interface only when not checking a single tunnel
+    fn allows_client(&self, interface: Option<&str>) -> bool {
+        match interface {
+            Some(name) => self.allows(name),
+            None => !self.single,
+        }
+    }
+
+    fn filter(&self, mut interface: Interface) -> Interface {
+        let link = self.inner.link_info(&interface.name);
+        // PPP links might be reported without the point-to-point flag
+        if interface.is_up()
+            && (interface.if_type == InterfaceType::Ppp
+                || kind::is_ppp(&interface.name, link.as_ref()))
+        {
+            interface.flags = (interface.flags | IFF_POINTOPOINT) & !IFF_BROADCAST;
+        }
+        if !self.allows(&interface.name)
+            || (!self.ip_tunnels && kind::is_ip_tunnel(&interface.name, link.as_ref()))
+        {
+            interface.flags &= !IFF_POINTOPOINT;
+        }
+        interface
+    }
+}
+
+impl InterfaceSource for FilteredSource<'_> {
+    fn default_interface(&self) -> Result<Interface, String> {
+        self.inner
+            .default_interface()
+            .map(|interface| self.filter(interface))
+    }
+
+    fn interfaces(&self) -> Vec<Interface> {
+        self.inner
+            .interfaces()
+            .into_iter()
+            .map(|interface| self.filter(interface))
+            .collect()
+    }
+
+    fn routes(&self) -> Vec<Route> {
+        self.inner.routes()
+    }
+
+    fn rules(&self) -> Vec<Rule> {
+        self.inner.rules()
+    }
+
+    fn nameservers(&self) -> Vec<Nameserver> {
+        self.inner.nameservers()
+    }
+
+    fn link_info(&self, name: &str) -> Option<LinkInfo> {
+        self.inner.link_info(name)
+    }
+
+    fn counters(&self, name: &str) -> Option<Counters> {
+        self.inner.counters(name)
+    }
+
+    fn wireguard_peers(&self, name: &str) -> Vec<wireguard::Peer> {
+        self.inner.wireguard_peers(name)
+    }
+
+    fn state_path(&self) -> Option<PathBuf> {
+        self.inner.state_path()
+    }
+
+    fn ipsec_policies(&self) -> Vec<ipsec::Policy> {
+        if self.single {
+            return Vec::new();
+        }
+        self.inner.ipsec_policies()
+    }
+
+    fn ipsec_states(&self) -> Vec<ipsec::State> {
+        if self.single {
+            return Vec::new();
+        }
+        self.inner.ipsec_states()
+    }
+
+    fn clients(&self) -> Vec<Client> {
+        self.inner
+            .clients()
+            .into_iter()
+            .filter(|client| self.allows_client(client.interface.as_deref()))
+            .collect()
+    }
+
+    fn connections(&self) -> Vec<Connection> {
+        self.inner
+            .connections()
+            .into_iter()
+            .filter(|connection| self.allows_client(connection.interface.as_deref()))
+            .collect()
+    }
+
+    fn ssid(&self, name: &str) -> Option<String> {
+        self.inner.ssid(name)
+    }
+
+    fn try_routes(&self) -> Result<Vec<Route>, VpnStatusError> {
+        self.inner.try_routes()
+    }
+
+    fn try_rules(&self) -> Result<Vec<Rule>, VpnStatusError> {
+        self.inner.try_rules()
+    }
+
+    fn try_wireguard_peers(&self, name: &str) -> Result<Vec<wireguard::Peer>, VpnStatusError> {
+        self.inner.try_wireguard_peers(name)
+    }
+
+    fn try_ipsec_policies(&self) -> Result<Vec<ipsec::Policy>, VpnStatusError> {
+        if self.single {
+            return Ok(Vec::new());
+        }
+        self.inner.try_ipsec_policies()
+    }
+
+    fn try_ipsec_states(&self) -> Result<Vec<ipsec::State>, VpnStatusError> {
+        if self.single {
+            return Ok(Vec::new());
+        }
+        self.inner.try_ipsec_states()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memory_default_interface() {
+        let mut eth0 = Interface::dummy();
+        eth0.name = "eth0".to_string();
+        eth0.default = true;
+        let mut tun0 = Interface::dummy();
+        tun0.name = "tun0".to_string();
+
+        let source = MemorySource::new(vec![tun0, eth0]);
+        assert_eq!(source.default_interface().unwrap().name, "eth0");
+        assert_eq!(source.interfaces().len(), 2);
+    }
+
+    #[test]
+    fn memory_no_default_interface() {
+        let source = MemorySource::default();
+        assert!(source.default_interface().is_err());
+        assert!(source.interfaces().is_empty());
+    }
+
+    #[test]
+    fn filtered_interfaces() {
+        let tunnel = |name: &str| {
+            let mut interface = Interface::dummy();
+            interface.name = name.to_string();
+            interface.flags = 0x1 | IFF_POINTOPOINT;
+            interface
+        };
+        let mut default = tunnel("tun0");
+        default.default = true;
+        let source = MemorySource::new(vec![default, tunnel("wg0"), tunnel("cni-podman0")]);
+
+        let filtered = FilteredSource::new(&source, vec![], vec!["cni*".to_string()]);
+        let tunnels: Vec<String> = filtered
+            .interfaces()
+            .into_iter()
+            .filter(|interface| interface.is_tun())
+            .map(|interface| interface.name)
+            .collect();
+        assert_eq!(tunnels, vec!["tun0", "wg0"]);
+        assert!(filtered.default_interface().unwrap().is_tun());
+
+        let filtered = FilteredSource::new(&source, vec!["wg*".to_string()], vec![]);
+        assert!(!filtered.default_interface().unwrap().is_tun());
+        assert!(filtered.allows("wg0"));
+        assert!(!filtered.allows("cni-podman0"));
+        assert_eq!(filtered.interfaces().len(), 3);
+    }
+
+    #[test]
+    fn filtered_clients() {
+        let connection = |id: &str, interface: Option<&str>| Connection {
+            id: id.to_string(),
+            uuid: format!("uuid-{}", id),
+            kind: "vpn".to_string(),
+            state: crate::networkmanager::ConnectionState::Activating,
+            interface: interface.map(str::to_string),
+        };
+        let source = MemorySource::new(vec![]).with_connections(vec![
+            connection("Office", Some("wg-work")),
+            connection("Home", Some("tun0")),
+            connection("Plugin", None),
+        ]);
+        let ids = |source: &FilteredSource| -> Vec<String> {
+            source
+                .connections()
+                .into_iter()
+                .map(|connection| connection.id)
+                .collect()
+        };
+
+        let filtered = FilteredSource::new(&source, vec![], vec!["tun*".to_string()]);
+        assert_eq!(ids(&filtered), vec!["Office", "Plugin"]);
+        let filtered =
+            FilteredSource::new(&source, vec!["wg-work".to_string()], vec![]).with_single(true);
+        assert_eq!(ids(&filtered), vec!["Office"]);
+    }
+
+    #[test]
+    fn point_to_point_interfaces() {
+        let interface = |name: &str, flags: u32| {
+            let mut interface = Interface::dummy();
+            interface.name = name.to_string();
+            interface.flags = flags;
+            interface
+        };
+        let source = MemorySource::new(vec![
+            interface("ppp0", 0x1 | IFF_BROADCAST),
+            interface("sstp-work", 0x1),
+            interface("gre1", 0x1 | IFF_POINTOPOINT),
+            interface("tunl0", 0x1 | IFF_POINTOPOINT),
+        ])
+        .with_links(HashMap::from([(
+            "sstp-work".to_string(),
+            LinkInfo {
+                hardware_type: Some(512),
+                ..Default::default()
+            },
+        )]));
+        let tunnels = |source: &FilteredSource| -> Vec<String> {
+            source
+                .interfaces()
+                .into_iter()
+                .filter(|interface| interface.is_tun())
+                .map(|interface| interface.name)
+                .collect()
+        };
+
+        let filtered = FilteredSource::new(&source, vec![], vec![]);
+        assert_eq!(tunnels(&filtered), vec!["ppp0", "sstp-work"]);
+        let filtered = filtered.with_ip_tunnels(true);
+        assert_eq!(
+            tunnels(&filtered),
+            vec!["ppp0", "sstp-work", "gre1", "tunl0"]
+        );
+    }
+
+    #[test]
+    fn memory_roundtrip() {
+        let mut wg0 = Interface::dummy();
+        wg0.name = "wg0".to_string();
+        let source = MemorySource::new(vec![wg0])
+            .with_routes(vec![Route::new("0.0.0.0/0".parse().unwrap(), "wg0")]);
+
+        let json = serde_json::to_string(&source).unwrap();
+        let source: MemorySource = serde_json::from_str(&json).unwrap();
+        assert_eq!(source.interfaces[0].name, "wg0");
+        assert_eq!(
+            source.routes(),
+            vec![Route::new("0.0.0.0/0".parse().unwrap(), "wg0")]
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use crate::traffic::TrafficState;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// State persisted between runs
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct State {
+    /// tunnel counters of the previous run
+    #[serde(default)]
+    pub traffic: TrafficState,
+    /// time the VPN connected in seconds since the unix epoch, none while disconnected
+    #[serde(default)]
+    pub connected_since: Option<u64>,
+}
+
+impl State {
+    /// Load the state from a file, starting over if it is missing or invalid
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save the state to a file
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let content = serde_json::to_string(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, content)
+    }
+
+    /// Track the connection, keeping the time it was established while it stays up
+    pub fn update_connection(&mut self, connected: bool, now: u64) {
+        self.connected_since = match self.connected_since {
+            Some(since) if connected => Some(since),
+            None if connected => Some(now),
+            _ => None,
+        };
+    }
+}
+
+/// Default location of the state, next to the lookup cache
+pub fn default_state_path() -> PathBuf {
+    PathBuf::from(public_ip_address::cache::get_cache_path(&Some(
+        "vpn_status_state.json".to_string(),
+    )))
+}
+
+/// Location of the state of a single interface check, next to the state of the full check.
+///
+/// The counters and connection time of one tunnel differ from those of all tunnels, so the
+/// interface name is appended to the file name, e.g. `state.json` becomes `state_wg0.json`.
+pub fn interface_state_path(path: &Path, interface: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let mut name = format!("{}_{}", stem, interface);
+    if let Some(extension) = path.extension() {
+        name = format!("{}.{}", name, extension.to_string_lossy());
+    }
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traffic::Counters;
+
+    #[test]
+    fn connection() {
+        let mut state = State::default();
+        state.update_connection(true, 100);
+        state.update_connection(true, 200);
+        assert_eq!(state.connected_since, Some(100));
+        state.update_connection(false, 300);
+        assert_eq!(state.connected_since, None);
+        state.update_connection(true, 400);
+        assert_eq!(state.connected_since, Some(400));
+    }
+
+    #[test]
+    fn interface_path() {
+        assert_eq!(
+            interface_state_path(Path::new("/tmp/state.json"), "wg0"),
+            PathBuf::from("/tmp/state_wg0.json")
+        );
+        assert_eq!(
+            interface_state_path(Path::new("state"), "tun0"),
+            PathBuf::from("state_tun0")
+        );
+    }
+
+    #[test]
+    fn persisted() {
+        let path =
+            std::env::temp_dir().join(format!("vpn_status_state_{}.json", std::process::id()));
+        let mut state = State::load(&path);
+        assert_eq!(state, State::default());
+
+        let counters = vec![(
+            "tun0".to_string(),
+            Counters {
+                rx_bytes: 1000,
+                tx_bytes: 1000,
+            },
+        )];
+        state.traffic.sample(&counters, 100.0, 60.0);
+        state.update_connection(true, 100);
+        state.save(&path).unwrap();
+
+        let mut state = State::load(&path);
+        assert_eq!(state.connected_since, Some(100));
+        let traffic = state.traffic.sample(&counters, 110.0, 60.0);
+        assert_eq!(traffic["tun0"].rx_rate, Some(0.0));
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,331 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Byte counters of an interface, as exposed in `/sys/class/net/<interface>/statistics`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Counters {
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+}
+
+/// Read the byte counters of an interface from a sysfs `class/net` directory
+pub fn read_counters(class_net: &Path, name: &str) -> Option<Counters> {
+    let statistics = class_net.join(name).join("statistics");
+    let read = |file: &str| -> Option<u64> {
+        std::fs::read_to_string(statistics.join(file))
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+    };
+    Some(Counters {
+        rx_bytes: read("rx_bytes")?,
+        tx_bytes: read("tx_bytes")?,
+    })
+}
+
+/// Traffic of a tunnel since the previous sample
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Traffic {
+    pub counters: Counters,
+    /// received bytes per second, unknown for the first sample
+    pub rx_rate: Option<f64>,
+    /// transmitted bytes per second, unknown for the first sample
+    pub tx_rate: Option<f64>,
+    /// whether the tunnel transmits without receiving anything for the stall timeout
+    pub stalled: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct Sample {
+    counters: Counters,
+    /// seconds since the unix epoch
+    timestamp: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct InterfaceState {
+    sample: Sample,
+    /// time the receive counter last increased
+    rx_changed_at: f64,
+    /// transmit counter at the time the receive counter last increased
+    tx_at_rx_change: u64,
+}
+
+impl InterfaceState {
+    fn new(sample: Sample) -> Self {
+        Self {
+            sample,
+            rx_changed_at: sample.timestamp,
+            tx_at_rx_change: sample.counters.tx_bytes,
+        }
+    }
+}
+
+/// Tunnel counters persisted between runs
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TrafficState {
+    interfaces: HashMap<String, InterfaceState>,
+}
+
+impl TrafficState {
+    /// Record the counters of an interface at the given time, in seconds since the unix
+    /// epoch, and get the traffic since the previous sample.
+    pub fn update(
+        &mut self,
+        name: &str,
+        counters: Counters,
+        now: f64,
+        stall_timeout: f64,
+    ) -> Traffic {
+        let sample = Sample {
+            counters,
+            timestamp: now,
+        };
+        let state = self
+            .interfaces
+            .entry(name.to_string())
+            .or_insert_with(|| InterfaceState::new(sample));
+        let previous = state.sample;
+
+        // counters start over when the interface is recreated
+        if counters.rx_bytes < previous.counters.rx_bytes
+            || counters.tx_bytes < previous.counters.tx_bytes
+        {
+            *state = InterfaceState::new(sample);
+        }
+
+        let elapsed = now - state.sample.timestamp;
+        let rate = |current: u64, previous: u64| {
+            (elapsed > 0.0).then(|| (current - previous) as f64 / elapsed)
+        };
+        let rx_rate = rate(counters.rx_bytes, state.sample.counters.rx_bytes);
+        let tx_rate = rate(counters.tx_bytes, state.sample.counters.tx_bytes);
+
+        if counters.rx_bytes > state.sample.counters.rx_bytes {
+            state.rx_changed_at = now;
+            state.tx_at_rx_change = counters.tx_bytes;
+        }
+        let stalled =
+            counters.tx_bytes > state.tx_at_rx_change && now - state.rx_changed_at >= stall_timeout;
+        state.sample = sample;
+
+        Traffic {
+            counters,
+            rx_rate,
+            tx_rate,
+            stalled,
+        }
+    }
+
+    /// Record the counters of all tunnels, forgetting the interfaces which are gone
+    pub fn sample(
+        &mut self,
+        counters: &[(String, Counters)],
+        now: f64,
+        stall_timeout: f64,
+    ) -> HashMap<String, Traffic> {
+        let traffic = counters
+            .iter()
+            .map(|(name, counters)| {
+                let traffic = self.update(name, *counters, now, stall_timeout);
+                (name.clone(), traffic)
+            })
+            .collect();
+        self.interfaces
+            .retain(|name, _| counters.iter().any(|(tunnel, _)| tunnel == name));
+        traffic
+    }
+}
+
+/// Format a rate in bytes per second
+pub fn format_rate(rate: f64) -> String {
+    const UNITS: [&str; 4] = ["B/s", "KB/s", "MB/s", "GB/s"];
+    let mut rate = rate;
+    let mut unit = 0;
+    while rate >= 1024.0 && unit < UNITS.len() - 1 {
+        rate /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{:.0} {}", rate, UNITS[unit])
+    } else {
+        format!("{:.1} {}", rate, UNITS[unit])
+    }
+}
+
+/// Format a duration with its two most significant units, e.g. `3m 12s`
+pub fn format_duration(duration: Duration) -> String {
+    DurationFormat::Short.format(duration)
+}
+
+/// Formatting of durations in the output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DurationFormat {
+    /// two most significant units, e.g. `3h 5m`
+    #[default]
+    Short,
+    /// two most significant units spelled out, e.g. `3 hours 5 minutes`
+    Long,
+    /// hours, minutes and seconds, e.g. `03:05:12`
+    Clock,
+    /// total seconds, e.g. `11112`
+    Seconds,
+}
+
+impl FromStr for DurationFormat {
+    type Err = String;
+    fn from_str(str: &str) -> Result<Self, Self::Err> {
+        match str.trim().to_lowercase().as_str() {
+            "short" => Ok(Self::Short),
+            "long" => Ok(Self::Long),
+            "clock" => Ok(Self::Clock),
+            "seconds" => Ok(Self::Seconds),
+            _ => Err(format!("unknown duration format: {}", str)),
+        }
+    }
+}
+
+impl DurationFormat {
+    pub fn format(&self, duration: Duration) -> String {
+        let seconds = duration.as_secs();
+        let units = [
+            (seconds / 86400, "d", "day"),
+            (seconds / 3600 % 24, "h", "hour"),
+            (seconds / 60 % 60, "m", "minute"),
+            (seconds % 60, "s", "second"),
+        ];
+        // start at the most significant unit which is not zero
+        let first = units
+            .iter()
+            .position(|(value, _, _)| *value > 0)
+            .unwrap_or(units.len() - 1);
+        let significant = &units[first..(first + 2).min(units.len())];
+        match self {
+            Self::Short => significant
+                .iter()
+                .map(|(value, unit, _)| format!("{}{}", value, unit))
+                .collect::<Vec<String>>()
+                .join(" "),
+            Self::Long => significant
+                .iter()
+                .map(|(value, _, unit)| match value {
+                    1 => format!("{} {}", value, unit),
+                    _ => format!("{} {}s", value, unit),
+                })
+                .collect::<Vec<String>>()
+                .join(" "),
+            Self::Clock => format!(
+                "{:02}:{:02}:{:02}",
+                seconds / 3600,
+                seconds / 60 % 60,
+                seconds % 60
+            ),
+            Self::Seconds => seconds.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counters(rx_bytes: u64, tx_bytes: u64) -> Counters {
+        Counters { rx_bytes, tx_bytes }
+    }
+
+    #[test]
+    fn sysfs() {
+        let class_net = Path::new("fixtures/sys/class/net");
+        assert_eq!(
+            read_counters(class_net, "tun0"),
+            Some(counters(1048576, 524288))
+        );
+        assert_eq!(read_counters(class_net, "wg-work"), None);
+    }
+
+    #[test]
+    fn rates() {
+        let mut state = TrafficState::default();
+        let first = state.update("tun0", counters(1000, 1000), 100.0, 60.0);
+        assert_eq!(first.rx_rate, None);
+        assert!(!first.stalled);
+
+        let second = state.update("tun0", counters(3000, 1500), 102.0, 60.0);
+        assert_eq!(second.rx_rate, Some(1000.0));
+        assert_eq!(second.tx_rate, Some(250.0));
+        assert!(!second.stalled);
+    }
+
+    #[test]
+    fn stalled() {
+        let mut state = TrafficState::default();
+        state.update("tun0", counters(1000, 1000), 100.0, 60.0);
+        // transmitting without any reply, but not for long enough
+        assert!(
+            !state
+                .update("tun0", counters(1000, 2000), 130.0, 60.0)
+                .stalled
+        );
+        assert!(
+            state
+                .update("tun0", counters(1000, 3000), 160.0, 60.0)
+                .stalled
+        );
+        // a reply clears the stall
+        assert!(
+            !state
+                .update("tun0", counters(1200, 3100), 170.0, 60.0)
+                .stalled
+        );
+        // idle tunnels are not stalled
+        assert!(
+            !state
+                .update("tun0", counters(1200, 3100), 500.0, 60.0)
+                .stalled
+        );
+    }
+
+    #[test]
+    fn recreated_interface() {
+        let mut state = TrafficState::default();
+        state.update("tun0", counters(5000, 5000), 100.0, 0.0);
+        let traffic = state.update("tun0", counters(10, 10), 110.0, 0.0);
+        assert_eq!(traffic.rx_rate, None);
+        assert!(!traffic.stalled);
+    }
+
+    #[test]
+    fn forget_removed_interfaces() {
+        let mut state = TrafficState::default();
+        state.sample(&[("tun0".to_string(), counters(1000, 1000))], 100.0, 60.0);
+        let traffic = state.sample(&[("tun1".to_string(), counters(10, 10))], 110.0, 60.0);
+        assert!(traffic.contains_key("tun1"));
+        assert_eq!(state.interfaces.keys().collect::<Vec<_>>(), vec!["tun1"]);
+    }
+
+    #[test]
+    fn formatting() {
+        assert_eq!(format_rate(0.0), "0 B/s");
+        assert_eq!(format_rate(512.4), "512 B/s");
+        assert_eq!(format_rate(1536.0), "1.5 KB/s");
+        assert_eq!(format_rate(3.0 * 1024.0 * 1024.0), "3.0 MB/s");
+
+        let duration = Duration::from_secs;
+        assert_eq!(format_duration(duration(0)), "0s");
+        assert_eq!(format_duration(duration(42)), "42s");
+        assert_eq!(format_duration(duration(192)), "3m 12s");
+        assert_eq!(format_duration(duration(3 * 3600 + 300)), "3h 5m");
+        assert_eq!(format_duration(duration(2 * 86400 + 3600)), "2d 1h");
+
+        let uptime = duration(3 * 3600 + 60 + 12);
+        assert_eq!(DurationFormat::Long.format(uptime), "3 hours 1 minute");
+        assert_eq!(DurationFormat::Clock.format(uptime), "03:01:12");
+        assert_eq!(DurationFormat::Seconds.format(uptime), "10872");
+        assert_eq!("Clock".parse(), Ok(DurationFormat::Clock));
+        assert!("weeks".parse::<DurationFormat>().is_err());
+    }
+}
@@ -0,0 +1,153 @@
+use crate::glob;
+use crate::routes::Route;
+use crate::source::InterfaceSource;
+use netdev::ipnet::IpNet;
+use netdev::Interface;
+use netdev::MacAddr;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::path::Path;
+
+/// Network the host is attached to, identified by its gateway and wireless network
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Network {
+    pub interface: String,
+    pub gateway: Option<IpAddr>,
+    /// hardware address of the gateway, from the neighbour table
+    pub gateway_mac: Option<MacAddr>,
+    /// name of the wireless network, none for wired links
+    pub ssid: Option<String>,
+}
+
+impl Network {
+    /// Identify the network behind the default interface, preferring the gateway of the
+    /// default route over the one netdev found
+    pub fn identify(
+        source: &dyn InterfaceSource,
+        interface: &Interface,
+        route: Option<&Route>,
+    ) -> Self {
+        let name = route.map_or(interface.name.as_str(), |route| &route.interface);
+        let device = interface.gateway.as_ref();
+        let gateway = route
+            .and_then(|route| route.gateway)
+            .or_else(|| device.and_then(|device| device.ipv4.first().copied().map(IpAddr::V4)));
+        let gateway_mac = device
+            .map(|device| device.mac_addr)
+            .filter(|mac| *mac != MacAddr::zero());
+        Self {
+            interface: name.to_string(),
+            gateway,
+            gateway_mac,
+            ssid: source.ssid(name),
+        }
+    }
+}
+
+/// Check whether a trusted network pattern matches the network.
+///
+/// A MAC address, with `:` or `-` separators, matches the hardware address of the gateway. An
+/// IP address or network in CIDR notation matches the gateway address. Anything else is a
+/// shell style pattern of the SSID, where `*` matches any sequence of characters.
+///
+/// # Example
+/// ```rust
+/// # use vpn_status_lib::trust::{self, Network};
+/// let network = Network {
+///     interface: "wlan0".to_string(),
+///     gateway: Some("192.168.178.1".parse().unwrap()),
+///     gateway_mac: Some("3c:a6:2f:0e:51:7d".parse().unwrap()),
+///     ssid: Some("Office 2.4GHz".to_string()),
+/// };
+/// assert!(trust::matches("192.168.178.0/24", &network));
+/// assert!(trust::matches("3C-A6-2F-0E-51-7D", &network));
+/// assert!(trust::matches("Office*", &network));
+/// assert!(!trust::matches("Home", &network));
+/// ```
+pub fn matches(pattern: &str, network: &Network) -> bool {
+    if let Ok(mac) = pattern.replace('-', ":").parse::<MacAddr>() {
+        return network.gateway_mac == Some(mac);
+    }
+    if let Ok(address) = pattern.parse::<IpAddr>() {
+        return network.gateway == Some(address);
+    }
+    if let Ok(net) = pattern.parse::<IpNet>() {
+        return network
+            .gateway
+            .is_some_and(|gateway| net.contains(&gateway));
+    }
+    network
+        .ssid
+        .as_ref()
+        .is_some_and(|ssid| glob::matches(pattern, ssid))
+}
+
+/// Get the first trusted network pattern matching the network
+pub fn trusted<'a>(patterns: &'a [String], network: &Network) -> Option<&'a String> {
+    patterns.iter().find(|pattern| matches(pattern, network))
+}
+
+/// Read the wireless interfaces listed in `/proc/net/wireless`
+pub fn read_wireless_interfaces(proc_net: &Path) -> Vec<String> {
+    let Ok(content) = std::fs::read_to_string(proc_net.join("wireless")) else {
+        return vec![];
+    };
+    // two header lines, followed by `name: status quality...` for each interface
+    content
+        .lines()
+        .skip(2)
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, _)| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn network() -> Network {
+        Network {
+            interface: "eth0".to_string(),
+            gateway: Some("10.20.0.1".parse().unwrap()),
+            gateway_mac: Some("00:1a:2b:3c:4d:5e".parse().unwrap()),
+            ssid: None,
+        }
+    }
+
+    #[test]
+    fn gateway_patterns() {
+        let network = network();
+        assert!(matches("10.20.0.1", &network));
+        assert!(matches("10.20.0.0/16", &network));
+        assert!(!matches("10.21.0.0/16", &network));
+        assert!(matches("00:1A:2B:3C:4D:5E", &network));
+        assert!(matches("00-1a-2b-3c-4d-5e", &network));
+        assert!(!matches("00:1a:2b:3c:4d:5f", &network));
+        // wired links never match an SSID
+        assert!(!matches("*", &network));
+    }
+
+    #[test]
+    fn ssid_patterns() {
+        let network = Network {
+            ssid: Some("Home".to_string()),
+            gateway_mac: None,
+            ..network()
+        };
+        assert!(matches("Home", &network));
+        assert!(!matches("home", &network));
+        assert!(!matches("00:1a:2b:3c:4d:5e", &network));
+
+        let patterns = vec!["Office*".to_string(), "H?me".to_string()];
+        assert_eq!(trusted(&patterns, &network), Some(&patterns[1]));
+        assert_eq!(trusted(&patterns[..1], &network), None);
+    }
+
+    #[test]
+    fn wireless_interfaces() {
+        let interfaces = read_wireless_interfaces(Path::new("fixtures/proc/net"));
+        assert_eq!(interfaces, vec!["wlan0"]);
+        assert!(read_wireless_interfaces(Path::new("fixtures/missing")).is_empty());
+    }
+}
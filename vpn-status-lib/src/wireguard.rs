@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::time::Duration;
+
+/// Peer of a WireGuard interface, as listed by `wg show`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Peer {
+    /// base64 encoded public key
+    pub public_key: String,
+    pub endpoint: Option<SocketAddr>,
+    /// time of the latest handshake in seconds since the unix epoch, none if it never happened
+    pub last_handshake: Option<u64>,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+}
+
+impl Peer {
+    /// Get the time since the latest handshake, given the current time in seconds since the
+    /// unix epoch
+    pub fn handshake_age(&self, now: u64) -> Option<Duration> {
+        self.last_handshake
+            .map(|handshake| Duration::from_secs(now.saturating_sub(handshake)))
+    }
+}
+
+/// Get the peer with the most recent handshake
+pub fn latest_peer(peers: &[Peer]) -> Option<&Peer> {
+    peers
+        .iter()
+        .filter(|peer| peer.last_handshake.is_some())
+        .max_by_key(|peer| peer.last_handshake)
+}
+
+/// Check whether no peer completed a handshake within the timeout.
+///
+/// WireGuard renews the session every two minutes while traffic flows, so an old handshake
+/// means the peers do not reach each other anymore, or the tunnel has been idle.
+pub fn handshake_expired(peers: &[Peer], now: u64, timeout: Duration) -> bool {
+    !peers.is_empty()
+        && !peers
+            .iter()
+            .any(|peer| peer.handshake_age(now).is_some_and(|age| age <= timeout))
+}
+
+/// Encode a key in base64, like the `wg` tool shows it
+pub(crate) fn encode_key(key: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::new();
+    for chunk in key.chunks(3) {
+        let bytes = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let value = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for index in 0..4 {
+            if index <= chunk.len() {
+                let sextet = (value >> (18 - 6 * index)) & 0x3f;
+                encoded.push(char::from(ALPHABET[sextet as usize]));
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(last_handshake: Option<u64>) -> Peer {
+        Peer {
+            public_key: encode_key(&[0; 32]),
+            endpoint: Some("198.51.100.7:51820".parse().unwrap()),
+            last_handshake,
+            rx_bytes: 0,
+            tx_bytes: 0,
+        }
+    }
+
+    #[test]
+    fn handshake() {
+        let timeout = Duration::from_secs(180);
+        let now = 1_700_000_000;
+        assert!(!handshake_expired(&[peer(Some(now - 30))], now, timeout));
+        assert!(handshake_expired(&[peer(Some(now - 300))], now, timeout));
+        assert!(handshake_expired(&[peer(None)], now, timeout));
+        assert!(!handshake_expired(
+            &[peer(None), peer(Some(now - 30))],
+            now,
+            timeout
+        ));
+        // interfaces without peers do not carry traffic anyway
+        assert!(!handshake_expired(&[], now, timeout));
+
+        let peers = vec![peer(Some(now - 300)), peer(Some(now - 30)), peer(None)];
+        assert_eq!(latest_peer(&peers), Some(&peers[1]));
+        assert_eq!(peers[1].handshake_age(now), Some(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn base64() {
+        assert_eq!(encode_key(b"wg"), "d2c=");
+        assert_eq!(encode_key(b"wgx"), "d2d4");
+        assert_eq!(encode_key(b"w"), "dw==");
+        assert_eq!(
+            encode_key(&[0; 32]),
+            "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA="
+        );
+    }
+}